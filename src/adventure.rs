@@ -0,0 +1,1260 @@
+//! Adventures, the individual steps of a saga.
+
+use crate::hooks::{Defaulted, Unhooked};
+use crate::lock::Lock;
+use crate::saga::{run_, undo_nested, Journal};
+#[cfg(feature = "std")]
+use crate::TimedOut;
+use crate::{CompArtifact, Failure, IrreversibleStepRolledBack, Saga, Warning};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::error::Error;
+use core::fmt;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::panic;
+#[cfg(feature = "std")]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(feature = "std")]
+use std::thread;
+
+/// Data a forward step hands to its own backward step, it is kept by
+/// the saga until the rollback.
+pub(crate) type Token = Box<dyn Any + Send>;
+/// The result of running a single step.
+type StepResult<T, E> = Result<T, Failure<T, E>>;
+/// The result of running a forward step along with its token.
+pub(crate) type Forwarded<T, E> = Result<(T, Option<Token>), Failure<T, E>>;
+/// The result of running a backward step along with the artifacts it
+/// created.
+pub(crate) type Reverted<T, E> = Result<(T, Vec<CompArtifact>), Failure<T, E>>;
+/// A backward step created with `Adventure::with_artifacts`.
+type Recording<T> = Arc<dyn Fn(T) -> (T, Vec<CompArtifact>) + Send + Sync>;
+type SharedForward<T, E> = Arc<dyn Fn(T) -> Forwarded<T, E> + Send + Sync>;
+type OnceForward<T, E> = Box<dyn FnOnce(T) -> Forwarded<T, E> + Send + Sync>;
+type SharedBackward<T, E> = Arc<dyn Fn(T, Option<Token>, &E) -> StepResult<T, E> + Send + Sync>;
+type OnceBackward<T, E> = Box<dyn FnOnce(T, Option<Token>, &E) -> StepResult<T, E> + Send + Sync>;
+type Predicate<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+type Validator<T, E> = Arc<dyn Fn(&T) -> Result<(), E> + Send + Sync>;
+type Commit<T> = Arc<dyn Fn(T) -> T + Send + Sync>;
+pub(crate) type ErrorContext<E> = Arc<dyn Fn(E, usize) -> E + Send + Sync>;
+type InPlaceForward<T, E> = Arc<dyn Fn(&mut T) -> Result<(), E> + Send + Sync>;
+type InPlaceBackward<T> = Arc<dyn Fn(&mut T) + Send + Sync>;
+/// Creates the error handed to the backward steps of an inverted saga.
+pub(crate) type Reason<E> = Arc<dyn Fn() -> E + Send + Sync>;
+
+/// What a forward step created with `Adventure::with_control` asks the
+/// saga to do next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control<T> {
+    /// Hands the accumulator on to the next adventure
+    Continue(T),
+    /// Ends the saga successfully with the accumulator, the remaining
+    /// adventures are not told
+    Complete(T),
+}
+
+/// Collects the compensations a forward step created with
+/// `Adventure::with_compensations` registers while it runs.
+pub struct Compensations<T> {
+    steps: Vec<Box<dyn FnOnce(T) -> T + Send>>,
+}
+
+impl<T> Compensations<T> {
+    fn new() -> Self {
+        Compensations { steps: Vec::new() }
+    }
+    /// Registers a compensation, like deleting a sub resource that was
+    /// just created
+    pub fn register_compensation<C>(&mut self, compensation: C)
+    where
+        C: FnOnce(T) -> T + Send + 'static,
+    {
+        self.steps.push(Box::new(compensation));
+    }
+    /// The number of registered compensations
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+    /// Whether no compensation was registered
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+    /// Runs the compensations in the reverse order they were registered
+    fn run(self, acc: T) -> T {
+        self.steps.into_iter().rev().fold(acc, |acc, step| step(acc))
+    }
+}
+
+impl<T> fmt::Debug for Compensations<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Compensations")
+            .field("len", &self.steps.len())
+            .finish()
+    }
+}
+
+/// The token of a forward step that completed the saga.
+pub(crate) struct Completed;
+
+/// The forward step of an adventure.
+pub(crate) enum Forward<T, E> {
+    Shared(SharedForward<T, E>),
+    Once(Option<OnceForward<T, E>>),
+}
+
+/// The backward step of an adventure.
+pub(crate) enum Backward<T, E> {
+    Shared(SharedBackward<T, E>),
+    Once(Option<OnceBackward<T, E>>),
+}
+
+/// The address of a step, used to tell steps apart when debugging.
+fn address<F: ?Sized>(step: &F) -> *const () {
+    step as *const F as *const ()
+}
+
+impl<T, E> Clone for Forward<T, E> {
+    fn clone(&self) -> Self {
+        match self {
+            Forward::Shared(step) => Forward::Shared(step.clone()),
+            Forward::Once(_) => panic!("{}", ONCE_CLONED),
+        }
+    }
+}
+
+impl<T, E> Clone for Backward<T, E> {
+    fn clone(&self) -> Self {
+        match self {
+            Backward::Shared(step) => Backward::Shared(step.clone()),
+            Backward::Once(_) => panic!("{}", ONCE_CLONED),
+        }
+    }
+}
+
+impl<T, E> fmt::Debug for Forward<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Forward::Shared(step) => write!(f, "Shared({:p})", address(&**step)),
+            Forward::Once(Some(step)) => write!(f, "Once({:p})", address(&**step)),
+            Forward::Once(None) => write!(f, "Spent"),
+        }
+    }
+}
+
+impl<T, E> fmt::Debug for Backward<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Backward::Shared(step) => write!(f, "Shared({:p})", address(&**step)),
+            Backward::Once(Some(step)) => write!(f, "Once({:p})", address(&**step)),
+            Backward::Once(None) => write!(f, "Spent"),
+        }
+    }
+}
+
+const ONCE_BORROWED: &str = "single use adventures can only be told with `Saga::tell_owned`";
+const ONCE_SPENT: &str = "single use adventure was told twice";
+const ONCE_CLONED: &str = "single use adventures can not be cloned";
+const ONCE_INVERTED: &str = "single use adventures can not be inverted";
+const NOT_IN_PLACE: &str = "only adventures created with `Adventure::in_place` without a commit step can be told in place";
+
+/// An adventure that can can forward succeed or fail and be reverted.
+/// Make sure that a failure includes enough info for THIS step itsel
+/// to be reverted
+pub struct Adventure<T, E = Box<dyn Error>> {
+    name: Option<String>,
+    phase: Option<Arc<str>>,
+    checkpoint: Option<String>,
+    tags: BTreeMap<String, String>,
+    forward: Forward<T, E>,
+    backward: Backward<T, E>,
+    retries: u32,
+    delay: Duration,
+    predicate: Option<Predicate<T>>,
+    validator: Option<Validator<T, E>>,
+    committed: Option<Predicate<T>>,
+    commit: Option<Commit<T>>,
+    context: Option<ErrorContext<E>>,
+    priority: i32,
+    weight: u32,
+    irreversible: bool,
+    /// Whether the backward step defers to the default of the saga
+    deferred: bool,
+    /// The backward step of an adventure created with
+    /// `Adventure::with_artifacts`, which hands back what it created
+    recording: Option<Recording<T>>,
+    /// The addresses of the functions the adventure was created from with
+    /// `Adventure::from_fns`
+    fns: Option<(usize, usize)>,
+    /// The steps of an adventure created with `Adventure::in_place`, for
+    /// telling it on a borrowed accumulator
+    in_place: Option<(InPlaceForward<T, E>, InPlaceBackward<T>)>,
+}
+
+impl<T, E> Adventure<T, E> {
+    /// Creates a new adventure with a forward and backward step.
+    ///
+    /// Both steps can be plain functions or closures that capture
+    /// their environment. Closures have to be `Send + Sync` like the
+    /// closures of all other adventures, so a saga can be shared between
+    /// threads.
+    pub fn new<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::fallible(forward, move |acc| Ok(backward(acc)))
+    }
+    /// Creates a new adventure whose steps can mutate the state they
+    /// captured, like collecting the ids of created resources.
+    ///
+    /// Each step is kept behind a lock so the saga can still be told
+    /// through a shared reference, also from several threads at once
+    /// which then take turns running the step. Telling the saga again
+    /// from within one of these steps deadlocks as the step is already
+    /// locked, and clones of the adventure share the captured state.
+    pub fn new_mut<F, B>(forward: F, backward: B) -> Self
+    where
+        F: FnMut(T) -> Result<T, Failure<T, E>> + Send + 'static,
+        B: FnMut(T) -> T + Send + 'static,
+    {
+        let forward = Lock::new(forward);
+        let backward = Lock::new(backward);
+        Adventure::new(
+            move |acc| forward.with(|forward| forward(acc)),
+            move |acc| backward.with(|backward| backward(acc)),
+        )
+    }
+    /// Creates a new adventure whose steps mutate the accumulator in
+    /// place instead of taking and returning it.
+    ///
+    /// When the forward step fails the accumulator is handed to the
+    /// rollback as the step left it. Told with `Saga::tell_in_place` the
+    /// steps are handed the accumulator of the caller by reference.
+    pub fn in_place<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(&mut T) -> Result<(), E> + Send + Sync + 'static,
+        B: Fn(&mut T) + Send + Sync + 'static,
+    {
+        let (forward, backward) = (Arc::new(forward), Arc::new(backward));
+        let (f, b) = (forward.clone(), backward.clone());
+        Adventure {
+            in_place: Some((forward, backward)),
+            ..Adventure::new(
+                move |mut acc| match f(&mut acc) {
+                    Ok(()) => Ok(acc),
+                    Err(error) => Err(Failure::new(acc, error)),
+                },
+                move |mut acc| {
+                    b(&mut acc);
+                    acc
+                },
+            )
+        }
+    }
+    /// Creates a new adventure with an alternative forward step that is
+    /// tried when the first one fails.
+    ///
+    /// The fallback is handed the state and error of the failed forward
+    /// step, only when it fails as well the saga is rolled back with its
+    /// failure. Either way `backward` compensates the adventure.
+    pub fn with_fallback<F, A, B>(forward: F, fallback: A, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        A: Fn(T, &E) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::new(
+            move |acc| forward(acc).or_else(|failure| fallback(failure.state, &failure.error)),
+            backward,
+        )
+    }
+    /// Creates a new adventure whose forward step can not fail.
+    pub fn infallible<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> T + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::new(move |acc| Ok(forward(acc)), backward)
+    }
+    /// Creates a new adventure from a step and its inverse, like adding
+    /// and subtracting the same amount, neither of which can fail.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds panics when the inverse does not turn the result
+    /// of the step for `sample` back into `sample`.
+    pub fn reversible<F, B>(forward: F, inverse: B, sample: T) -> Self
+    where
+        T: Clone + PartialEq + fmt::Debug,
+        F: Fn(T) -> T + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        debug_assert_eq!(
+            inverse(forward(sample.clone())),
+            sample,
+            "the inverse does not revert the step"
+        );
+        Adventure::infallible(forward, inverse)
+    }
+    /// Creates a new adventure whose effect can not be compensated, like
+    /// sending an email.
+    ///
+    /// When a rollback reaches the adventure the accumulator is handed
+    /// on unchanged and an `IrreversibleStepRolledBack` error is reported
+    /// as a failed compensation at its index, so operators know the
+    /// effect has to be undone by hand. Unlike other failed compensations
+    /// the rollback goes on with the adventures before it. This also
+    /// happens when the forward step of the adventure itself fails.
+    pub fn irreversible<F>(forward: F) -> Self
+    where
+        E: From<IrreversibleStepRolledBack>,
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+    {
+        Adventure {
+            irreversible: true,
+            ..Adventure::fallible(forward, |acc| Err(Failure::from_error(acc, IrreversibleStepRolledBack)))
+        }
+    }
+    /// Creates a new adventure without a backward step of its own, when
+    /// it is rolled back the default of the saga set with
+    /// `Saga::with_default_backward` runs instead.
+    ///
+    /// Without a default the rollback fails at the adventure with an
+    /// `IrreversibleStepRolledBack` error.
+    pub fn forward_only<F>(forward: F) -> Self
+    where
+        E: From<IrreversibleStepRolledBack>,
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+    {
+        Adventure {
+            deferred: true,
+            ..Adventure::fallible(forward, |acc| Err(Failure::from_error(acc, IrreversibleStepRolledBack)))
+        }
+    }
+    /// Creates a new adventure that needs no compensation, like reading
+    /// data, its backward step hands the accumulator on unchanged.
+    pub fn pure<F>(forward: F) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+    {
+        Adventure::new(forward, |acc| acc)
+    }
+    /// Creates a new adventure whose backward step can fail as well.
+    ///
+    /// When a backward step fails the rollback stops at this adventure,
+    /// the failure returned by the saga then carries the compensation
+    /// error next to the original one.
+    pub fn fallible<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| forward(acc).map(|acc| (acc, None)))),
+            Backward::Shared(Arc::new(move |acc, _, _| backward(acc))),
+        )
+    }
+    /// Creates a new adventure whose backward step is handed the error
+    /// that caused the saga to fail.
+    ///
+    /// The same error is shared by every backward step of the rollback.
+    pub fn new_with_error<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T, &E) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| forward(acc).map(|acc| (acc, None)))),
+            Backward::Shared(Arc::new(move |acc, _, error| Ok(backward(acc, error)))),
+        )
+    }
+    /// Creates a new adventure whose forward step produces a separate
+    /// compensation value next to the accumulator.
+    ///
+    /// The compensation value, like a transaction id to refund, is kept
+    /// by the saga and handed to the backward step of this adventure
+    /// during the rollback. When the forward step itself fails there is
+    /// no compensation value so the backward step is skipped, the same
+    /// goes for adventures told before a saga was resumed with
+    /// `Saga::tell_from`.
+    pub fn with_compensation<C, F, B>(forward: F, backward: B) -> Self
+    where
+        C: Send + 'static,
+        F: Fn(T) -> Result<(T, C), Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T, C) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| {
+                forward(acc).map(|(acc, c)| (acc, Some(Box::new(c) as Token)))
+            })),
+            Backward::Shared(Arc::new(move |acc, token, _| {
+                match token.and_then(|token| token.downcast::<C>().ok()) {
+                    Some(c) => Ok(backward(acc, *c)),
+                    None => Ok(acc),
+                }
+            })),
+        )
+    }
+    /// Creates a new adventure whose forward step hands any data to its
+    /// backward step next to the accumulator, the backward step
+    /// downcasts it to what it expects.
+    ///
+    /// This is the untyped form of `Adventure::with_compensation` for
+    /// forward steps that hand over different kinds of data. The data is
+    /// kept by the saga until the rollback, like there the backward step
+    /// is skipped when there is no data.
+    pub fn with_any_compensation<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<(T, Box<dyn Any + Send>), Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T, Box<dyn Any + Send>) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| forward(acc).map(|(acc, data)| (acc, Some(data))))),
+            Backward::Shared(Arc::new(move |acc, token, _| match token {
+                Some(data) => Ok(backward(acc, data)),
+                None => Ok(acc),
+            })),
+        )
+    }
+    /// Creates a new adventure whose backward step is handed a snapshot
+    /// of the input of its forward step next to the accumulator.
+    ///
+    /// The input is cloned before the forward step runs and kept by the
+    /// saga until the rollback. Like for `Adventure::with_compensation`
+    /// the backward step is skipped when there is no snapshot, that is
+    /// when the forward step itself failed or the adventure was told
+    /// before a saga was resumed with `Saga::tell_from`.
+    pub fn with_input_snapshot<F, B>(forward: F, backward: B) -> Self
+    where
+        T: Clone + Send + 'static,
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T, T) -> T + Send + Sync + 'static,
+    {
+        Adventure::with_compensation(
+            move |acc: T| {
+                let input = acc.clone();
+                forward(acc).map(|acc| (acc, input))
+            },
+            backward,
+        )
+    }
+    /// Creates a new adventure whose forward step registers its own
+    /// compensations, like one for each of the sub resources it created.
+    ///
+    /// The compensations are kept by the saga and run in the reverse
+    /// order they were registered when the adventure is rolled back.
+    /// When the forward step itself fails the compensations it registered
+    /// so far run right away on the state of its failure, before the
+    /// saga rolls back the adventures told before it.
+    pub fn with_compensations<F>(forward: F) -> Self
+    where
+        T: 'static,
+        F: Fn(T, &mut Compensations<T>) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| {
+                let mut compensations = Compensations::new();
+                match forward(acc, &mut compensations) {
+                    Ok(acc) => Ok((acc, Some(Box::new(compensations) as Token))),
+                    Err(failure) => Err(failure.map_state(|acc| compensations.run(acc))),
+                }
+            })),
+            Backward::Shared(Arc::new(move |acc, token, _| {
+                match token.and_then(|token| token.downcast::<Compensations<T>>().ok()) {
+                    Some(compensations) => Ok(compensations.run(acc)),
+                    None => Ok(acc),
+                }
+            })),
+        )
+    }
+    /// Creates a new adventure whose forward step returns warnings next to
+    /// its result.
+    ///
+    /// The warnings are returned by `Saga::tell_with_warnings` once the
+    /// saga succeeded, other ways of telling the saga drop them. When the
+    /// saga is rolled back the warnings are discarded.
+    pub fn with_warnings<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<(T, Vec<Warning>), Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| {
+                forward(acc).map(|(acc, warnings)| (acc, Some(Box::new(warnings) as Token)))
+            })),
+            Backward::Shared(Arc::new(move |acc, _, _| Ok(backward(acc)))),
+        )
+    }
+    /// Creates a new adventure whose backward step returns records of
+    /// what it did next to the accumulator, like the refund it issued.
+    ///
+    /// The artifacts are collected while the saga is rolled back and
+    /// returned with `Failure::artifacts`, the saga sets the index of
+    /// each. Artifacts of adventures in nested sagas are not collected.
+    pub fn with_artifacts<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> (T, Vec<CompArtifact>) + Send + Sync + 'static,
+    {
+        let backward = Arc::new(backward);
+        let recording = backward.clone();
+        Adventure {
+            recording: Some(recording),
+            ..Adventure::new(forward, move |acc| backward(acc).0)
+        }
+    }
+    /// Creates a new adventure whose forward step can end the saga early,
+    /// like when it finds that there is nothing left to do.
+    ///
+    /// When the forward step returns `Control::Complete` the saga
+    /// succeeds right away with the accumulator, none of the remaining
+    /// adventures are told and nothing is rolled back. The commit steps
+    /// of the adventures told so far run as usual. Inside a nested saga
+    /// only the nested saga is completed.
+    pub fn with_control<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<Control<T>, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| match forward(acc)? {
+                Control::Continue(acc) => Ok((acc, None)),
+                Control::Complete(acc) => Ok((acc, Some(Box::new(Completed) as Token))),
+            })),
+            Backward::Shared(Arc::new(move |acc, _, _| Ok(backward(acc)))),
+        )
+    }
+    /// Creates a new adventure whose forward step is retried when it
+    /// fails.
+    ///
+    /// The forward step is attempted up to `retries` more times waiting
+    /// `delay` between each attempt, the saga only rolls back once all
+    /// retries are exhausted. Each retry is handed the state of the
+    /// previous failure.
+    #[cfg(feature = "std")]
+    pub fn with_retry<F, B>(forward: F, backward: B, retries: u32, delay: Duration) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure {
+            retries,
+            delay,
+            ..Adventure::new(forward, backward)
+        }
+    }
+    /// Creates a new adventure whose forward step fails when it does
+    /// not finish within `timeout`.
+    ///
+    /// The forward step runs on a worker thread, when it times out the
+    /// adventure fails with a `TimedOut` error and its backward step is
+    /// handed the state the forward step was called with. The worker
+    /// thread can not be stopped and keeps running in the background.
+    /// When the forward step panics the panic is resumed on the thread
+    /// telling the saga, like a panic of any other step.
+    ///
+    /// Since the result has to be sent back from the worker thread the
+    /// error type has to be `Send`, the default `Box<dyn Error>` is not
+    /// so `Box<dyn Error + Send + Sync>` can be used instead.
+    #[cfg(feature = "std")]
+    pub fn with_timeout<F, B>(forward: F, backward: B, timeout: Duration) -> Self
+    where
+        T: Clone + Send + 'static,
+        E: From<TimedOut> + Send + 'static,
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        let forward = Arc::new(forward);
+        Adventure::new(
+            move |acc: T| {
+                let (tx, rx) = mpsc::channel();
+                let f = forward.clone();
+                let input = acc.clone();
+                let worker = thread::spawn(move || {
+                    let _ = tx.send(f(acc));
+                });
+                match rx.recv_timeout(timeout) {
+                    Ok(res) => res,
+                    Err(RecvTimeoutError::Timeout) => {
+                        Err(Failure::new(input, E::from(TimedOut { timeout })))
+                    }
+                    // the worker only hangs up without sending when it panicked
+                    Err(RecvTimeoutError::Disconnected) => match worker.join() {
+                        Ok(()) => unreachable!("the worker sends before it returns"),
+                        Err(payload) => panic::resume_unwind(payload),
+                    },
+                }
+            },
+            backward,
+        )
+    }
+    /// Creates a new adventure whose forward step can expand the saga
+    /// with additional adventures.
+    ///
+    /// The adventures returned by the forward step are told right after
+    /// it, before the next adventure of the saga. When one of them fails
+    /// they are reverted first and then the backward step of this
+    /// adventure runs. When a later adventure fails they are reverted in
+    /// reverse order right before the backward step of this adventure. A
+    /// failing compensation of an added adventure is reported at the
+    /// index of this adventure, the added adventures are not seen by
+    /// observers.
+    pub fn expanding<F, B>(forward: F, backward: B) -> Self
+    where
+        T: 'static,
+        E: 'static,
+        F: Fn(T) -> Result<(T, Vec<Adventure<T, E>>), Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| {
+                let (acc, more) = forward(acc)?;
+                let (acc, told) = run_(more, &mut Unhooked, acc)?;
+                Ok((acc, Some(Box::new(told) as Token)))
+            })),
+            Backward::Shared(Arc::new(move |acc, token, error| {
+                let acc = match token.and_then(|token| token.downcast::<Journal<Self>>().ok()) {
+                    Some(told) => undo_nested(*told, &mut Defaulted(None), error, acc)?,
+                    None => acc,
+                };
+                Ok(backward(acc))
+            })),
+        )
+    }
+    /// Creates an adventure that tells a whole saga as a single step.
+    ///
+    /// When the inner saga fails it reverts its own adventures, this
+    /// adventure then fails with the error and state of the inner saga
+    /// and the outer saga reverts the adventures before it. When the
+    /// rollback of the inner saga stalls the outer saga stops as well
+    /// and reports the compensation failure at the index of this
+    /// adventure. When a later adventure fails the told adventures of the
+    /// inner saga are reverted in reverse order.
+    ///
+    /// # Panics
+    ///
+    /// Panics when told if the inner saga contains single use adventures.
+    pub fn from_saga(saga: Saga<T, E>) -> Self
+    where
+        T: 'static,
+        E: 'static,
+    {
+        let saga = Arc::new(saga);
+        let inner = saga.clone();
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| {
+                let (acc, told) = run_(&saga.adventures, &mut Defaulted::new(&saga.settings), acc)?;
+                let told: Vec<_> = told.into_iter().map(|(i, _, token)| (i, token)).collect();
+                Ok((acc, Some(Box::new(told) as Token)))
+            })),
+            Backward::Shared(Arc::new(move |acc, token, error| {
+                match token.and_then(|token| token.downcast::<Vec<(usize, Option<Token>)>>().ok()) {
+                    Some(told) => {
+                        let told = told
+                            .into_iter()
+                            .map(|(i, token)| (i, &inner.adventures[i], token))
+                            .collect();
+                        undo_nested(told, &mut Defaulted::new(&inner.settings), error, acc)
+                    }
+                    None => Ok(acc),
+                }
+            })),
+        )
+    }
+    /// Creates a single use adventure, both steps are called at most
+    /// once so they can move captured resources.
+    ///
+    /// Sagas containing single use adventures have to be told with
+    /// `Saga::tell_owned`.
+    pub fn once<F, B>(forward: F, backward: B) -> Self
+    where
+        F: FnOnce(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: FnOnce(T) -> T + Send + Sync + 'static,
+    {
+        Adventure::from_steps(
+            Forward::Once(Some(Box::new(move |acc| {
+                forward(acc).map(|acc| (acc, None))
+            }))),
+            Backward::Once(Some(Box::new(move |acc, _, _| Ok(backward(acc))))),
+        )
+    }
+    /// Creates a new adventure that is only told when the predicate
+    /// holds for the accumulator it would be handed.
+    ///
+    /// When the predicate does not hold the adventure is skipped, it is
+    /// then also skipped when the saga is rolled back.
+    pub fn conditional<P, F, B>(predicate: P, forward: F, backward: B) -> Self
+    where
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Adventure {
+            predicate: Some(Arc::new(predicate)),
+            ..Adventure::new(forward, backward)
+        }
+    }
+    pub(crate) fn from_steps(forward: Forward<T, E>, backward: Backward<T, E>) -> Self {
+        Adventure {
+            name: None,
+            phase: None,
+            checkpoint: None,
+            tags: BTreeMap::new(),
+            forward,
+            backward,
+            retries: 0,
+            delay: Duration::from_secs(0),
+            predicate: None,
+            validator: None,
+            committed: None,
+            commit: None,
+            context: None,
+            priority: 0,
+            weight: 1,
+            irreversible: false,
+            deferred: false,
+            recording: None,
+            fns: None,
+            in_place: None,
+        }
+    }
+    /// Gives the adventure a human readable name
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+    /// Tags the adventure with `key` set to `value`, like the system it
+    /// talks to, so tooling can find it with `Saga::steps_with_tag`.
+    /// Tagging it with the same key again replaces the value.
+    pub fn tag<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+    /// The tags of the adventure, sorted by their keys
+    pub fn tags(&self) -> &BTreeMap<String, String> {
+        &self.tags
+    }
+    /// Gives the adventure a validator that checks without side
+    /// effects whether it would succeed, it is used by `Saga::dry_run`
+    pub fn with_validator<V>(mut self, validator: V) -> Self
+    where
+        V: Fn(&T) -> Result<(), E> + Send + Sync + 'static,
+    {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+    /// Gives the adventure an idempotency check that tells whether the
+    /// effect of the forward step is already present, like a payment
+    /// that was charged before a crash.
+    ///
+    /// When the check holds the forward step is skipped and the
+    /// accumulator handed on unchanged. The adventure still counts as
+    /// told so its backward step runs when the saga is rolled back.
+    pub fn with_idempotency_check<P>(mut self, check: P) -> Self
+    where
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.committed = Some(Arc::new(check));
+        self
+    }
+    /// Gives the adventure a commit step that finalizes what the
+    /// forward step prepared.
+    ///
+    /// Commit steps only run once every forward step of the saga
+    /// succeeded, in the order the adventures were told. When the saga
+    /// fails no commit step runs and the saga is rolled back as usual.
+    /// The commit steps of the adventures of a nested saga never run.
+    pub fn with_commit<C>(mut self, commit: C) -> Self
+    where
+        C: Fn(T) -> T + Send + Sync + 'static,
+    {
+        self.commit = Some(Arc::new(commit));
+        self
+    }
+    /// Gives the adventure a step that wraps the error of the saga with
+    /// context about what its backward step cleaned up, it is handed the
+    /// error and the index of the adventure.
+    ///
+    /// It runs once the backward step of the adventure succeeded. The
+    /// failure of the saga then holds an error wrapped by every reverted
+    /// adventure in the order they were reverted, the outermost layer is
+    /// the adventure reverted last. Backward steps are still handed the
+    /// error the saga failed with, and adventures of a nested saga do not
+    /// wrap it.
+    pub fn with_error_context<C>(mut self, context: C) -> Self
+    where
+        C: Fn(E, usize) -> E + Send + Sync + 'static,
+    {
+        self.context = Some(Arc::new(context));
+        self
+    }
+    /// Marks the adventure as a checkpoint, once its forward step
+    /// succeeded `Saga::tell_checkpointed` only rolls the saga back to
+    /// here
+    pub fn with_checkpoint<S: Into<String>>(mut self, name: S) -> Self {
+        self.checkpoint = Some(name.into());
+        self
+    }
+    /// Gives the adventure a rollback priority, adventures with a higher
+    /// priority are compensated first.
+    ///
+    /// All adventures start with a priority of 0, adventures with the
+    /// same priority are compensated in reverse order. This allows
+    /// compensations whose dependencies differ from the forward order,
+    /// like deleting children before the parent created first.
+    pub fn with_rollback_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+    /// Gives the adventure a weight telling how much work it is
+    /// compared to other adventures, all adventures start with a weight
+    /// of 1
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+    /// The name of the adventure if it was given one
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// The weight of the adventure
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+    /// The name of the checkpoint if the adventure was marked as one
+    pub fn checkpoint(&self) -> Option<&str> {
+        self.checkpoint.as_deref()
+    }
+    /// The phase the adventure was grouped under with `Saga::phase`
+    pub fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+    pub(crate) fn in_phase(mut self, phase: Arc<str>) -> Self {
+        self.phase = Some(phase);
+        self
+    }
+    /// Creates a new adventure from plain function pointers
+    pub fn from_fns(forward: fn(T) -> Result<T, Failure<T, E>>, backward: fn(T) -> T) -> Self
+    where
+        T: 'static,
+        E: 'static,
+    {
+        Adventure {
+            fns: Some((forward as usize, backward as usize)),
+            ..Adventure::new(forward, backward)
+        }
+    }
+    /// The inverse of this adventure, backward steps turned forward
+    /// steps are handed the error `reason` creates.
+    pub(crate) fn inverse(&self, reason: Reason<E>) -> Self
+    where
+        T: 'static,
+        E: 'static,
+    {
+        let forward = match &self.backward {
+            Backward::Shared(step) => step.clone(),
+            Backward::Once(_) => panic!("{}", ONCE_INVERTED),
+        };
+        let backward = match &self.forward {
+            Forward::Shared(step) => step.clone(),
+            Forward::Once(_) => panic!("{}", ONCE_INVERTED),
+        };
+        Adventure {
+            name: self.name.clone(),
+            ..Adventure::from_steps(
+                Forward::Shared(Arc::new(move |acc| {
+                    forward(acc, None, &reason()).map(|acc| (acc, None))
+                })),
+                Backward::Shared(Arc::new(move |acc, _, _| backward(acc).map(|(acc, _)| acc))),
+            )
+        }
+    }
+    pub(crate) fn validate(&self, acc: &T) -> Result<(), E> {
+        self.validator.as_ref().map_or(Ok(()), |v| v(acc))
+    }
+    fn applies(&self, acc: &T) -> bool {
+        self.predicate.as_ref().is_none_or(|p| p(acc))
+    }
+    fn committed(&self, acc: &T) -> bool {
+        self.committed.as_ref().is_some_and(|c| c(acc))
+    }
+    fn commit(&self, acc: T) -> T {
+        match &self.commit {
+            Some(commit) => commit(acc),
+            None => acc,
+        }
+    }
+    fn forward(&self, acc: T) -> Forwarded<T, E> {
+        match &self.forward {
+            Forward::Shared(f) => f(acc),
+            Forward::Once(_) => panic!("{}", ONCE_BORROWED),
+        }
+    }
+    /// Runs the forward step including all retries, returns the number
+    /// of attempts next to the result
+    fn attempt(&self, acc: T) -> (Forwarded<T, E>, u32) {
+        let mut res = self.forward(acc);
+        let mut attempts = 1;
+        for _ in 0..self.retries {
+            match res {
+                Err(Failure { state, .. }) => {
+                    #[cfg(feature = "std")]
+                    thread::sleep(self.delay);
+                    res = self.forward(state);
+                    attempts += 1;
+                }
+                ok => return (ok, attempts),
+            }
+        }
+        (res, attempts)
+    }
+    fn attempt_once(&mut self, acc: T) -> (Forwarded<T, E>, u32) {
+        match &mut self.forward {
+            Forward::Once(f) => (f.take().expect(ONCE_SPENT)(acc), 1),
+            Forward::Shared(_) => self.attempt(acc),
+        }
+    }
+    fn backward(&self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E> {
+        if let Some(recording) = &self.recording {
+            return Ok(recording(acc));
+        }
+        match &self.backward {
+            Backward::Shared(f) => f(acc, token, error).map(|acc| (acc, Vec::new())),
+            Backward::Once(_) => panic!("{}", ONCE_BORROWED),
+        }
+    }
+    fn backward_once(&mut self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E> {
+        match &mut self.backward {
+            Backward::Once(f) => {
+                let reverted = f.take().expect(ONCE_SPENT)(acc, token, error);
+                reverted.map(|acc| (acc, Vec::new()))
+            }
+            Backward::Shared(_) => Adventure::backward(self, acc, token, error),
+        }
+    }
+}
+
+/// Clones share the steps of the original adventure.
+///
+/// # Panics
+///
+/// Panics when cloning single use adventures created with
+/// `Adventure::once`.
+impl<T, E> Clone for Adventure<T, E> {
+    fn clone(&self) -> Self {
+        Adventure {
+            name: self.name.clone(),
+            phase: self.phase.clone(),
+            checkpoint: self.checkpoint.clone(),
+            tags: self.tags.clone(),
+            forward: self.forward.clone(),
+            backward: self.backward.clone(),
+            retries: self.retries,
+            delay: self.delay,
+            predicate: self.predicate.clone(),
+            validator: self.validator.clone(),
+            committed: self.committed.clone(),
+            commit: self.commit.clone(),
+            context: self.context.clone(),
+            priority: self.priority,
+            weight: self.weight,
+            irreversible: self.irreversible,
+            deferred: self.deferred,
+            recording: self.recording.clone(),
+            fns: self.fns,
+            in_place: self.in_place.clone(),
+        }
+    }
+}
+
+/// Whether two optional steps are the same, that is both are missing or
+/// both are clones of one another.
+pub(crate) fn same<F: ?Sized>(a: &Option<Arc<F>>, b: &Option<Arc<F>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => address(&**a) == address(&**b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl<T, E> Forward<T, E> {
+    fn address(&self) -> Option<*const ()> {
+        match self {
+            Forward::Shared(step) => Some(address(&**step)),
+            Forward::Once(step) => step.as_ref().map(|step| address(&**step)),
+        }
+    }
+}
+
+impl<T, E> Backward<T, E> {
+    fn address(&self) -> Option<*const ()> {
+        match self {
+            Backward::Shared(step) => Some(address(&**step)),
+            Backward::Once(step) => step.as_ref().map(|step| address(&**step)),
+        }
+    }
+}
+
+/// Two adventures are equal when they have the same steps and settings.
+///
+/// Adventures created with `Adventure::from_fns` have the same steps when
+/// they were created from the same functions, the steps of all other
+/// adventures are only the same for clones of one adventure. Closures
+/// are never compared by what they do. The compiler does not guarantee
+/// that a function has a single address, or that different functions
+/// have different ones, so this is meant for testing how sagas are put
+/// together.
+impl<T, E> PartialEq for Adventure<T, E> {
+    fn eq(&self, other: &Self) -> bool {
+        let steps = match (self.fns, other.fns) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                self.forward.address() == other.forward.address()
+                    && self.backward.address() == other.backward.address()
+            }
+        };
+        steps
+            && self.name == other.name
+            && self.phase == other.phase
+            && self.checkpoint == other.checkpoint
+            && self.tags == other.tags
+            && self.retries == other.retries
+            && self.delay == other.delay
+            && self.priority == other.priority
+            && self.weight == other.weight
+            && self.irreversible == other.irreversible
+            && self.deferred == other.deferred
+            && same(&self.predicate, &other.predicate)
+            && same(&self.validator, &other.validator)
+            && same(&self.committed, &other.committed)
+            && same(&self.commit, &other.commit)
+            && same(&self.context, &other.context)
+    }
+}
+
+impl<T, E> fmt::Debug for Adventure<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Adventure")
+            .field("name", &self.name)
+            .field("forward", &self.forward)
+            .field("backward", &self.backward)
+            .field("retries", &self.retries)
+            .field("delay", &self.delay)
+            .field("conditional", &self.predicate.is_some())
+            .field("validated", &self.validator.is_some())
+            .field("idempotent", &self.committed.is_some())
+            .field("two_phase", &self.commit.is_some())
+            .field("error_context", &self.context.is_some())
+            .field("priority", &self.priority)
+            .field("weight", &self.weight)
+            .field("irreversible", &self.irreversible)
+            .field("deferred", &self.deferred)
+            .field("phase", &self.phase)
+            .field("checkpoint", &self.checkpoint)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
+/// An adventure as it is told by a saga, either borrowed from the saga
+/// or owned when the saga is consumed.
+pub(crate) trait Told<T, E> {
+    /// Whether the adventure should be told for this accumulator
+    fn applies(&self, acc: &T) -> bool;
+    /// Whether the effect of the forward step is already present
+    fn committed(&self, _acc: &T) -> bool {
+        false
+    }
+    /// The name of the adventure if it was given one
+    fn name(&self) -> Option<&str> {
+        None
+    }
+    /// The phase the adventure belongs to if it was grouped under one
+    fn phase(&self) -> Option<&str> {
+        None
+    }
+    /// The rollback priority of the adventure
+    fn priority(&self) -> i32;
+    /// Whether the effect of the adventure can not be compensated
+    fn irreversible(&self) -> bool {
+        false
+    }
+    /// Whether the backward step defers to the default of the saga
+    fn deferred(&self) -> bool {
+        false
+    }
+    /// Wraps the error of the saga once the backward step succeeded
+    fn error_context(&self) -> Option<ErrorContext<E>> {
+        None
+    }
+    /// Runs the forward step including all retries
+    fn forward(&mut self, acc: T) -> Forwarded<T, E>;
+    /// Runs the forward step like `forward` and returns how often it
+    /// was attempted next to the result
+    fn forward_counted(&mut self, acc: T) -> (Forwarded<T, E>, u32) {
+        (self.forward(acc), 1)
+    }
+    /// Runs the commit step
+    fn commit(&mut self, acc: T) -> T {
+        acc
+    }
+    /// Runs the backward step, returns the artifacts it created next to
+    /// the accumulator
+    fn backward(&mut self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E>;
+}
+
+impl<T, E> Told<T, E> for &Adventure<T, E> {
+    fn applies(&self, acc: &T) -> bool {
+        Adventure::applies(self, acc)
+    }
+    fn committed(&self, acc: &T) -> bool {
+        Adventure::committed(self, acc)
+    }
+    fn name(&self) -> Option<&str> {
+        Adventure::name(self)
+    }
+    fn phase(&self) -> Option<&str> {
+        Adventure::phase(self)
+    }
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+    fn irreversible(&self) -> bool {
+        self.irreversible
+    }
+    fn deferred(&self) -> bool {
+        self.deferred
+    }
+    fn error_context(&self) -> Option<ErrorContext<E>> {
+        self.context.clone()
+    }
+    fn commit(&mut self, acc: T) -> T {
+        Adventure::commit(self, acc)
+    }
+    fn forward(&mut self, acc: T) -> Forwarded<T, E> {
+        self.attempt(acc).0
+    }
+    fn forward_counted(&mut self, acc: T) -> (Forwarded<T, E>, u32) {
+        self.attempt(acc)
+    }
+    fn backward(&mut self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E> {
+        Adventure::backward(self, acc, token, error)
+    }
+}
+
+/// An adventure created with `Adventure::in_place` told on a borrowed
+/// accumulator.
+pub(crate) struct InPlace<'s, T, E> {
+    adventure: &'s Adventure<T, E>,
+    forward: &'s InPlaceForward<T, E>,
+    backward: &'s InPlaceBackward<T>,
+}
+
+impl<'s, T, E> InPlace<'s, T, E> {
+    /// Borrows the steps of the adventure
+    ///
+    /// # Panics
+    ///
+    /// Panics when the adventure was not created with
+    /// `Adventure::in_place` or has a commit step, which both take the
+    /// accumulator by value.
+    pub(crate) fn new(adventure: &'s Adventure<T, E>) -> Self {
+        match &adventure.in_place {
+            Some((forward, backward)) if adventure.commit.is_none() => InPlace {
+                adventure,
+                forward,
+                backward,
+            },
+            _ => panic!("{}", NOT_IN_PLACE),
+        }
+    }
+}
+
+impl<'a, T, E> Told<&'a mut T, E> for InPlace<'_, T, E> {
+    fn applies(&self, acc: &&'a mut T) -> bool {
+        self.adventure.applies(acc)
+    }
+    fn committed(&self, acc: &&'a mut T) -> bool {
+        self.adventure.committed(acc)
+    }
+    fn name(&self) -> Option<&str> {
+        self.adventure.name()
+    }
+    fn phase(&self) -> Option<&str> {
+        self.adventure.phase()
+    }
+    fn priority(&self) -> i32 {
+        self.adventure.priority
+    }
+    fn error_context(&self) -> Option<ErrorContext<E>> {
+        self.adventure.context.clone()
+    }
+    fn forward(&mut self, acc: &'a mut T) -> Forwarded<&'a mut T, E> {
+        self.forward_counted(acc).0
+    }
+    fn forward_counted(&mut self, acc: &'a mut T) -> (Forwarded<&'a mut T, E>, u32) {
+        let mut res = (self.forward)(acc);
+        let mut attempts = 1;
+        for _ in 0..self.adventure.retries {
+            if res.is_ok() {
+                break;
+            }
+            #[cfg(feature = "std")]
+            thread::sleep(self.adventure.delay);
+            res = (self.forward)(acc);
+            attempts += 1;
+        }
+        match res {
+            Ok(()) => (Ok((acc, None)), attempts),
+            Err(error) => (Err(Failure::new(acc, error)), attempts),
+        }
+    }
+    fn backward(&mut self, acc: &'a mut T, _: Option<Token>, _: &E) -> Reverted<&'a mut T, E> {
+        (self.backward)(acc);
+        Ok((acc, Vec::new()))
+    }
+}
+
+impl<T, E> Told<T, E> for Adventure<T, E> {
+    fn applies(&self, acc: &T) -> bool {
+        Adventure::applies(self, acc)
+    }
+    fn committed(&self, acc: &T) -> bool {
+        Adventure::committed(self, acc)
+    }
+    fn name(&self) -> Option<&str> {
+        Adventure::name(self)
+    }
+    fn phase(&self) -> Option<&str> {
+        Adventure::phase(self)
+    }
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+    fn irreversible(&self) -> bool {
+        self.irreversible
+    }
+    fn deferred(&self) -> bool {
+        self.deferred
+    }
+    fn error_context(&self) -> Option<ErrorContext<E>> {
+        self.context.clone()
+    }
+    fn commit(&mut self, acc: T) -> T {
+        Adventure::commit(self, acc)
+    }
+    fn forward(&mut self, acc: T) -> Forwarded<T, E> {
+        self.attempt_once(acc).0
+    }
+    fn forward_counted(&mut self, acc: T) -> (Forwarded<T, E>, u32) {
+        self.attempt_once(acc)
+    }
+    fn backward(&mut self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E> {
+        self.backward_once(acc, token, error)
+    }
+}
@@ -0,0 +1,37 @@
+//! Records created by backward steps.
+
+use alloc::string::String;
+use core::fmt;
+
+/// A record a backward step created with `Adventure::with_artifacts`
+/// while compensating, like the id of a refund it issued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompArtifact {
+    pub(crate) index: usize,
+    record: String,
+}
+
+impl CompArtifact {
+    /// Creates a new artifact from a record
+    pub fn new<S: Into<String>>(record: S) -> Self {
+        CompArtifact {
+            index: 0,
+            record: record.into(),
+        }
+    }
+    /// The record the backward step created
+    pub fn record(&self) -> &str {
+        &self.record
+    }
+    /// The index of the adventure whose backward step created the
+    /// artifact, it is set when the saga is rolled back
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for CompArtifact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step {}: {}", self.index, self.record)
+    }
+}
@@ -0,0 +1,493 @@
+//! Sagas made of asynchronous adventures.
+//!
+//! An `AsyncSaga` behaves exactly like a `Saga` but each forward and
+//! backward step returns a future. The steps are awaited one after
+//! another and on failure the compensations are awaited in strictly
+//! reverse order. No runtime is required, the returned future can be
+//! driven by any executor.
+//!
+//! Dropping the future of `tell_async` while a step is in flight stops
+//! the saga without compensating anything, since `Drop` can not await
+//! the backward steps. A saga that may have to be stopped halfway is
+//! told with `tell_abortable` instead, its handle has an `abort` method
+//! that hands out the future of the rollback.
+
+use crate::{Cancelled, Failure, TimedOut};
+use std::error::Error;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// A boxed future as returned by asynchronous steps.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// A boxed future that may borrow the saga.
+type LocalFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// The forward step of an asynchronous adventure.
+type Forward<T, E> = Box<dyn Fn(T) -> BoxFuture<Result<T, Failure<T, E>>>>;
+/// The backward step of an asynchronous adventure.
+type Backward<T> = Box<dyn Fn(T) -> BoxFuture<T>>;
+
+/// A saga of many asynchronous adventures that can be told.
+pub struct AsyncSaga<T, E = Box<dyn Error>> {
+    adventures: Vec<AsyncAdventure<T, E>>,
+}
+
+impl<T, E> AsyncSaga<T, E> {
+    /// Creates a new saga from a vector of asynchronous adventures
+    pub fn new(adventures: Vec<AsyncAdventure<T, E>>) -> Self {
+        AsyncSaga { adventures }
+    }
+    /// Tells a saga, awaiting each step in turn, reverts on failure
+    /// and returns either the result or error
+    pub async fn tell_async(&self, acc: T) -> Result<T, Failure<T, E>> {
+        let mut acc = acc;
+        for (i, adventure) in self.adventures.iter().enumerate() {
+            match adventure.forward(acc).await {
+                Ok(acc1) => acc = acc1,
+                Err(Failure { state, error, .. }) => return Err(self.revert(i, state, error).await),
+            }
+        }
+        Ok(acc)
+    }
+    /// Tells a saga like `tell_async` but returns a handle that can
+    /// abort it, the handle is a future resolving to the result.
+    ///
+    /// The input of each forward step is cloned before the step runs so
+    /// the step can be compensated when the saga is aborted while it is
+    /// in flight.
+    pub fn tell_abortable(&self, acc: T) -> AbortableTell<'_, T, E>
+    where
+        T: Clone,
+    {
+        AbortableTell {
+            saga: self,
+            stage: Stage::Start(acc),
+        }
+    }
+    /// Runs the backward steps from the adventure at `index` down to the
+    /// first one.
+    async fn revert(&self, index: usize, acc: T, error: E) -> Failure<T, E> {
+        let mut acc = acc;
+        for adventure in self.adventures[..=index].iter().rev() {
+            acc = adventure.backward(acc).await;
+        }
+        Failure {
+            failed_at: index,
+            ..Failure::new(acc, error)
+        }
+    }
+    fn revert_boxed(&self, index: usize, acc: T, error: E) -> LocalFuture<'_, Failure<T, E>> {
+        Box::pin(self.revert(index, acc, error))
+    }
+}
+
+/// The steps of an abortable tell.
+enum Stage<'a, T, E> {
+    Start(T),
+    Forward {
+        index: usize,
+        input: T,
+        step: BoxFuture<Result<T, Failure<T, E>>>,
+    },
+    Rollback(LocalFuture<'a, Failure<T, E>>),
+    Done,
+}
+
+/// A saga that is being told and can be aborted, see
+/// `AsyncSaga::tell_abortable`.
+///
+/// Awaiting the handle tells the saga to the end. Dropping it stops the
+/// saga without compensating the adventures told so far, to roll them
+/// back the saga has to be aborted and the rollback awaited.
+pub struct AbortableTell<'a, T, E = Box<dyn Error>> {
+    saga: &'a AsyncSaga<T, E>,
+    stage: Stage<'a, T, E>,
+}
+
+// the accumulator is never pinned, only the boxed futures are
+impl<T, E> Unpin for AbortableTell<'_, T, E> {}
+
+impl<'a, T: Clone, E> AbortableTell<'a, T, E> {
+    /// Stops telling the saga and returns the future of the rollback.
+    ///
+    /// The adventure in flight is compensated with the input of its
+    /// forward step, then the adventures told before it in reverse
+    /// order. The failure holds a `Cancelled` error and `failed_at` is
+    /// the index of the adventure in flight. When the saga already
+    /// failed on its own its rollback is carried on instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the saga already finished.
+    pub fn abort(self) -> LocalFuture<'a, Failure<T, E>>
+    where
+        E: From<Cancelled>,
+    {
+        let saga = self.saga;
+        match self.stage {
+            Stage::Start(acc) => Box::pin(async move { Failure::from_error(acc, Cancelled) }),
+            Stage::Forward { index, input, .. } => saga.revert_boxed(index, input, E::from(Cancelled)),
+            Stage::Rollback(rollback) => rollback,
+            Stage::Done => panic!("the saga already finished"),
+        }
+    }
+    /// Starts the forward step of the adventure at `index` or finishes
+    /// the saga when there is none left.
+    fn start(&mut self, index: usize, acc: T) -> Option<T> {
+        match self.saga.adventures.get(index) {
+            Some(adventure) => {
+                self.stage = Stage::Forward {
+                    index,
+                    input: acc.clone(),
+                    step: adventure.forward(acc),
+                };
+                None
+            }
+            None => {
+                self.stage = Stage::Done;
+                Some(acc)
+            }
+        }
+    }
+}
+
+impl<T: Clone, E> Future for AbortableTell<'_, T, E> {
+    type Output = Result<T, Failure<T, E>>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            match &mut this.stage {
+                Stage::Start(_) => {
+                    if let Stage::Start(acc) = std::mem::replace(&mut this.stage, Stage::Done) {
+                        if let Some(acc) = this.start(0, acc) {
+                            return Poll::Ready(Ok(acc));
+                        }
+                    }
+                }
+                Stage::Forward { index, step, .. } => match step.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(acc)) => {
+                        let next = *index + 1;
+                        if let Some(acc) = this.start(next, acc) {
+                            return Poll::Ready(Ok(acc));
+                        }
+                    }
+                    Poll::Ready(Err(Failure { state, error, .. })) => {
+                        let index = *index;
+                        this.stage = Stage::Rollback(this.saga.revert_boxed(index, state, error));
+                    }
+                },
+                Stage::Rollback(rollback) => {
+                    let failure = match rollback.as_mut().poll(cx) {
+                        Poll::Pending => return Poll::Pending,
+                        Poll::Ready(failure) => failure,
+                    };
+                    this.stage = Stage::Done;
+                    return Poll::Ready(Err(failure));
+                }
+                Stage::Done => panic!("the saga already finished"),
+            }
+        }
+    }
+}
+
+/// An asynchronous adventure, the counterpart of `Adventure` whose
+/// steps return futures.
+pub struct AsyncAdventure<T, E = Box<dyn Error>> {
+    forward: Forward<T, E>,
+    backward: Backward<T>,
+}
+
+impl<T, E> AsyncAdventure<T, E> {
+    /// Creates a new asynchronous adventure with a forward and backward step
+    pub fn new<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> BoxFuture<Result<T, Failure<T, E>>> + 'static,
+        B: Fn(T) -> BoxFuture<T> + 'static,
+    {
+        AsyncAdventure {
+            forward: Box::new(forward),
+            backward: Box::new(backward),
+        }
+    }
+    /// Creates a new adventure whose forward step is synchronous while
+    /// its backward step returns a future, like a slow cleanup API.
+    ///
+    /// The forward step runs right away when it is told and its result
+    /// is handed on in a ready future. Since a rollback can happen at any
+    /// step the saga still has to be told with `AsyncSaga::tell_async`,
+    /// so telling it is asynchronous as a whole.
+    pub fn with_sync_forward<F, B>(forward: F, backward: B) -> Self
+    where
+        T: 'static,
+        E: 'static,
+        F: Fn(T) -> Result<T, Failure<T, E>> + 'static,
+        B: Fn(T) -> BoxFuture<T> + 'static,
+    {
+        AsyncAdventure::new(
+            move |acc| -> BoxFuture<Result<T, Failure<T, E>>> { Box::pin(future::ready(forward(acc))) },
+            backward,
+        )
+    }
+    /// Creates a new asynchronous adventure whose forward step fails
+    /// when its future does not resolve within `timeout`.
+    ///
+    /// When the step times out its future is dropped, the adventure
+    /// fails with a `TimedOut` error and the backward step is handed
+    /// the state the forward step was called with. The timer runs on
+    /// its own thread so this works with any executor.
+    pub fn with_timeout<F, B>(forward: F, backward: B, timeout: Duration) -> Self
+    where
+        T: Clone + 'static,
+        E: From<TimedOut> + 'static,
+        F: Fn(T) -> BoxFuture<Result<T, Failure<T, E>>> + 'static,
+        B: Fn(T) -> BoxFuture<T> + 'static,
+    {
+        AsyncAdventure::new(
+            move |acc: T| -> BoxFuture<Result<T, Failure<T, E>>> {
+                let input = acc.clone();
+                let step = Timeout::new(forward(acc), timeout);
+                Box::pin(async move {
+                    match step.await {
+                        Some(res) => res,
+                        None => Err(Failure::new(input, E::from(TimedOut { timeout }))),
+                    }
+                })
+            },
+            backward,
+        )
+    }
+    fn forward(&self, acc: T) -> BoxFuture<Result<T, Failure<T, E>>> {
+        (self.forward)(acc)
+    }
+    fn backward(&self, acc: T) -> BoxFuture<T> {
+        (self.backward)(acc)
+    }
+}
+
+/// Shared state between a `Timeout` and its timer thread.
+#[derive(Default)]
+struct Timer {
+    fired: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+/// Resolves to `None` when the inner future does not resolve in time.
+struct Timeout<F> {
+    future: F,
+    timeout: Duration,
+    timer: Option<Arc<Timer>>,
+}
+
+impl<F> Timeout<F> {
+    fn new(future: F, timeout: Duration) -> Self {
+        Timeout {
+            future,
+            timeout,
+            timer: None,
+        }
+    }
+}
+
+impl<F: Future + Unpin> Future for Timeout<F> {
+    type Output = Option<F::Output>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(res) = Pin::new(&mut self.future).poll(cx) {
+            return Poll::Ready(Some(res));
+        }
+        let timeout = self.timeout;
+        let timer = self.timer.get_or_insert_with(|| {
+            let timer = Arc::new(Timer::default());
+            let fire = timer.clone();
+            thread::spawn(move || {
+                thread::sleep(timeout);
+                fire.fired.store(true, Ordering::SeqCst);
+                if let Some(waker) = fire.waker.lock().unwrap().take() {
+                    waker.wake();
+                }
+            });
+            timer
+        });
+        *timer.waker.lock().unwrap() = Some(cx.waker().clone());
+        if timer.fired.load(Ordering::SeqCst) {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::StupidError;
+    use crate::AsyncAdventure;
+    use crate::AsyncSaga;
+    use crate::BoxFuture;
+    use crate::Failure;
+    use std::error::Error;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(res) => return res,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn stupid() -> Box<dyn Error> {
+        Box::new(StupidError { stupid: true })
+    }
+    fn inc2(i: i32) -> BoxFuture<Result<i32, Failure<i32>>> {
+        Box::pin(async move {
+            if i >= 2 {
+                Err(Failure::new(i + 1, stupid()))
+            } else {
+                Ok(i + 1)
+            }
+        })
+    }
+    fn dec(i: i32) -> BoxFuture<i32> {
+        Box::pin(async move { i - 1 })
+    }
+    #[test]
+    fn good_sage() {
+        let saga = AsyncSaga::new(vec![
+            AsyncAdventure::new(inc2, dec),
+            AsyncAdventure::new(inc2, dec),
+        ]);
+        match block_on(saga.tell_async(0)) {
+            Ok(res) => assert_eq!(res, 2),
+            Err(_) => unimplemented!(),
+        }
+    }
+    #[test]
+    fn bad_sage() {
+        let saga = AsyncSaga::new(vec![
+            AsyncAdventure::new(inc2, dec),
+            AsyncAdventure::new(inc2, dec),
+            AsyncAdventure::new(inc2, dec),
+        ]);
+        match block_on(saga.tell_async(0)) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn timed_out_sage() {
+        use crate::TimedOut;
+        use std::time::Duration;
+        fn never(_: i32) -> BoxFuture<Result<i32, Failure<i32>>> {
+            Box::pin(std::future::pending())
+        }
+        let saga = AsyncSaga::new(vec![
+            AsyncAdventure::with_timeout(inc2, dec, Duration::from_millis(200)),
+            AsyncAdventure::with_timeout(never, dec, Duration::from_millis(10)),
+        ]);
+        match block_on(saga.tell_async(0)) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, -1);
+                assert!(failure.to_error().downcast_ref::<TimedOut>().is_some());
+            }
+        }
+    }
+    #[test]
+    fn abortable_sage() {
+        let saga = AsyncSaga::new(vec![
+            AsyncAdventure::new(inc2, dec),
+            AsyncAdventure::new(inc2, dec),
+        ]);
+        assert_eq!(block_on(saga.tell_abortable(0)).ok(), Some(2));
+        assert_eq!(block_on(saga.tell_abortable(2)).unwrap_err().state, 2);
+    }
+    #[test]
+    fn aborted_sage() {
+        use crate::Cancelled;
+        fn never(_: i32) -> BoxFuture<Result<i32, Failure<i32>>> {
+            Box::pin(std::future::pending())
+        }
+        let saga = AsyncSaga::new(vec![
+            AsyncAdventure::new(inc2, dec),
+            AsyncAdventure::new(never, dec),
+            AsyncAdventure::new(inc2, dec),
+        ]);
+        let mut tell = saga.tell_abortable(0);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(Pin::new(&mut tell).poll(&mut cx).is_pending());
+        let failure = block_on(tell.abort());
+        assert_eq!(failure.failed_at(), 1);
+        assert_eq!(failure.state, -1);
+        assert!(failure.to_error().downcast_ref::<Cancelled>().is_some());
+    }
+    #[test]
+    fn sync_forward_sage() {
+        fn inc(i: i32) -> Result<i32, Failure<i32>> {
+            if i >= 2 {
+                Err(Failure::new(i + 1, stupid()))
+            } else {
+                Ok(i + 1)
+            }
+        }
+        let saga = AsyncSaga::new(vec![
+            AsyncAdventure::with_sync_forward(inc, dec),
+            AsyncAdventure::with_sync_forward(inc, dec),
+        ]);
+        assert_eq!(block_on(saga.tell_async(0)).ok(), Some(2));
+        match block_on(saga.tell_async(1)) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 1),
+        }
+    }
+    #[test]
+    fn reverse_rollback() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let step = |n: i32| {
+            let log = log.clone();
+            AsyncAdventure::new(
+                move |i: i32| -> BoxFuture<Result<i32, Failure<i32>>> {
+                    Box::pin(async move {
+                        if n == 3 {
+                            Err(Failure::new(i, stupid()))
+                        } else {
+                            Ok(i + 1)
+                        }
+                    })
+                },
+                move |i: i32| -> BoxFuture<i32> {
+                    let log = log.clone();
+                    Box::pin(async move {
+                        log.lock().unwrap().push(n);
+                        i - 1
+                    })
+                },
+            )
+        };
+        let saga = AsyncSaga::new(vec![step(1), step(2), step(3)]);
+        assert!(block_on(saga.tell_async(0)).is_err());
+        assert_eq!(*log.lock().unwrap(), vec![3, 2, 1]);
+    }
+}
@@ -0,0 +1,104 @@
+//! A fluent way of assembling sagas.
+
+use crate::{Adventure, Failure, Saga};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+
+/// Builds a saga step by step, the adventures are told in the order
+/// they were added.
+///
+/// ```
+/// use aud::{Failure, SagaBuilder};
+///
+/// fn inc(i: i32) -> Result<i32, Failure<i32>> {
+///     Ok(i + 1)
+/// }
+/// fn dec(i: i32) -> i32 {
+///     i - 1
+/// }
+///
+/// let saga = SagaBuilder::new()
+///     .step(inc, dec)
+///     .step_named("second increment", inc, dec)
+///     .build();
+/// assert!(saga.tell(0).ok() == Some(2));
+/// ```
+pub struct SagaBuilder<T, E = Box<dyn Error>> {
+    adventures: Vec<Adventure<T, E>>,
+}
+
+impl<T, E> SagaBuilder<T, E> {
+    /// Creates a builder without any adventures
+    pub fn new() -> Self {
+        SagaBuilder {
+            adventures: Vec::new(),
+        }
+    }
+    /// Adds an already constructed adventure
+    pub fn adventure(mut self, adventure: Adventure<T, E>) -> Self {
+        self.adventures.push(adventure);
+        self
+    }
+    /// Adds an adventure with a forward and backward step
+    pub fn step<F, B>(self, forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        self.adventure(Adventure::new(forward, backward))
+    }
+    /// Adds an adventure with a human readable name used when reporting
+    /// on the saga
+    pub fn step_named<S, F, B>(self, name: S, forward: F, backward: B) -> Self
+    where
+        S: Into<String>,
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        self.adventure(Adventure::new(forward, backward).with_name(name))
+    }
+    /// Turns the builder into a saga
+    pub fn build(self) -> Saga<T, E> {
+        Saga::new(self.adventures)
+    }
+}
+
+impl<T, E> Default for SagaBuilder<T, E> {
+    fn default() -> Self {
+        SagaBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc};
+    use crate::Failure;
+    use crate::SagaBuilder;
+
+    fn double(i: i32) -> Result<i32, Failure<i32>> {
+        Ok(i * 2)
+    }
+    fn half(i: i32) -> i32 {
+        i / 2
+    }
+    #[test]
+    fn keeps_order() {
+        let saga = SagaBuilder::new().step(inc, dec).step(double, half).build();
+        match saga.tell(1) {
+            Ok(res) => assert_eq!(res, 4),
+            Err(_) => unimplemented!(),
+        }
+    }
+    #[test]
+    fn names_steps() {
+        let saga = SagaBuilder::new()
+            .step_named("inc", inc, dec)
+            .step(double, half)
+            .build();
+        let mut adventures = saga.iter();
+        assert_eq!(adventures.next().unwrap().name(), Some("inc"));
+        assert_eq!(adventures.next().unwrap().name(), None);
+    }
+}
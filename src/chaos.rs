@@ -0,0 +1,71 @@
+//! Failures injected into a saga for chaos testing.
+
+use crate::hooks::Hooks;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::error::Error;
+
+/// Forces forward steps to fail while a saga is told with
+/// `Saga::tell_with_chaos`, to test the compensations without changing
+/// the steps themselves.
+///
+/// The forward step of an adventure whose index has a fault is not run,
+/// the adventure fails with the given error instead. Its backward step
+/// and those of the adventures before it run as usual.
+pub struct ChaosPolicy<E = Box<dyn Error>> {
+    faults: Vec<(usize, E)>,
+}
+
+impl<E> ChaosPolicy<E> {
+    /// Creates a policy without any faults
+    pub fn new() -> Self {
+        ChaosPolicy { faults: Vec::new() }
+    }
+    /// Makes the forward step of the adventure at `index` fail with
+    /// `error`
+    pub fn fail_at(mut self, index: usize, error: E) -> Self {
+        self.faults.push((index, error));
+        self
+    }
+}
+
+impl<E> Default for ChaosPolicy<E> {
+    fn default() -> Self {
+        ChaosPolicy::new()
+    }
+}
+
+impl<T, E> Hooks<T, E> for ChaosPolicy<E> {
+    fn injected(&mut self, index: usize) -> Option<E> {
+        let fault = self.faults.iter().position(|(at, _)| *at == index)?;
+        Some(self.faults.swap_remove(fault).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc};
+    use crate::Adventure;
+    use crate::ChaosPolicy;
+    use crate::Saga;
+
+    #[test]
+    fn injects_failure() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+        ]);
+        let chaos = ChaosPolicy::new().fail_at(2, "chaos");
+        match saga.tell_with_chaos(0, chaos) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(*failure.error(), "chaos");
+                // the step at 2 never ran but is compensated
+                assert_eq!(failure.state, -1);
+            }
+        }
+        assert_eq!(saga.tell_with_chaos(0, ChaosPolicy::new()).ok(), Some(3));
+    }
+}
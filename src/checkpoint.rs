@@ -0,0 +1,85 @@
+//! Checkpoints that bound how far a saga is rolled back.
+
+use crate::hooks::Hooks;
+use alloc::vec::Vec;
+
+/// Keeps the adventures up to the last checkpoint that was reached when
+/// a saga is rolled back.
+pub(crate) struct Checkpoints<'a> {
+    /// The checkpoint of each adventure
+    names: Vec<Option<&'a str>>,
+    /// The index of the last checkpoint whose forward step succeeded
+    reached: Option<usize>,
+}
+
+impl<'a> Checkpoints<'a> {
+    pub(crate) fn new(names: Vec<Option<&'a str>>) -> Self {
+        Checkpoints {
+            names,
+            reached: None,
+        }
+    }
+    /// The name of the last checkpoint that was reached
+    pub(crate) fn reached(&self) -> Option<&'a str> {
+        self.reached.and_then(|index| self.names[index])
+    }
+}
+
+impl<T, E> Hooks<T, E> for Checkpoints<'_> {
+    fn after_forward(&mut self, index: usize, _acc: &T) {
+        if self.names.get(index).copied().flatten().is_some() {
+            self.reached = Some(index);
+        }
+    }
+    fn keeps(&self, index: usize) -> bool {
+        self.reached.is_some_and(|reached| index <= reached)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc};
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Saga;
+
+    fn fail(i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        Err(Failure::new(i + 1, "failed"))
+    }
+    #[test]
+    fn rolls_back_to_checkpoint() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec).with_checkpoint("reserved"),
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec).with_checkpoint("charged"),
+            Adventure::new(inc, dec),
+            Adventure::new(fail, dec),
+        ]);
+        let (failure, checkpoint) = saga.tell_checkpointed(0).unwrap_err();
+        assert_eq!(checkpoint, Some("charged"));
+        assert_eq!(failure.state, 4);
+        assert_eq!(failure.failed_at(), 5);
+        assert_eq!(failure.rolled_back(), 2);
+    }
+    #[test]
+    fn rolls_back_fully_without_checkpoint() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(fail, dec),
+            Adventure::new(inc, dec).with_checkpoint("never"),
+        ]);
+        let (failure, checkpoint) = saga.tell_checkpointed(0).unwrap_err();
+        assert_eq!(checkpoint, None);
+        assert_eq!(failure.state, 0);
+        assert_eq!(saga.tell(0).unwrap_err().state, 0);
+    }
+    #[test]
+    fn tells_past_checkpoints() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_checkpoint("first"),
+            Adventure::new(inc, dec),
+        ]);
+        assert_eq!(saga.tell_checkpointed(0).ok(), Some(2));
+    }
+}
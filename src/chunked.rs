@@ -0,0 +1,136 @@
+//! Sagas that process a large source one chunk at a time.
+//!
+//! A chunked saga splits the items of a source into chunks and tells its
+//! adventures for each chunk, in the order the chunks are pulled from the
+//! source. Chunks are pulled lazily, so the source is never collected as
+//! a whole up front. When telling a chunk fails every chunk processed
+//! before it is reverted too, in reverse order, so they all have to be
+//! kept until the saga finishes: a chunked saga holds the processed
+//! chunks along with the adventures told for them, the items that were
+//! never pulled from the source are not held.
+
+use crate::saga::tell_all;
+use crate::{Failure, Saga};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+/// The processed chunks or the failure of a chunked saga.
+type Told<U, E> = Result<Vec<Vec<U>>, Failure<Vec<Vec<U>>, E>>;
+
+/// A saga that is told for each chunk of a source.
+pub struct ChunkedSaga<U, E = Box<dyn Error>> {
+    saga: Saga<Vec<U>, E>,
+    chunk_size: usize,
+}
+
+impl<U, E> Saga<Vec<U>, E> {
+    /// Turns a saga over chunks into one that splits a source into
+    /// chunks of `chunk_size` items, the last chunk may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `chunk_size` is 0.
+    pub fn chunked(self, chunk_size: usize) -> ChunkedSaga<U, E> {
+        assert!(chunk_size > 0, "chunks must not be empty");
+        ChunkedSaga {
+            saga: self,
+            chunk_size,
+        }
+    }
+}
+
+impl<U, E> ChunkedSaga<U, E> {
+    /// The number of items in each chunk
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+    /// Tells the saga for each chunk of `source` and returns the
+    /// processed chunks in order.
+    ///
+    /// When telling a chunk fails the state of the failure holds the
+    /// reverted chunks followed by the failed one as its own rollback
+    /// left it, `failed_at` is the index of the failed chunk. The rest
+    /// of the source is never pulled.
+    pub fn tell<I>(&self, source: I) -> Told<U, E>
+    where
+        I: IntoIterator<Item = U>,
+    {
+        let mut chunks = Chunks {
+            source: source.into_iter(),
+            size: self.chunk_size,
+        };
+        tell_all(&self.saga, &mut chunks)
+    }
+}
+
+impl<U, E> fmt::Debug for ChunkedSaga<U, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChunkedSaga")
+            .field("saga", &self.saga)
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
+
+/// Splits a source into chunks.
+struct Chunks<I> {
+    source: I,
+    size: usize,
+}
+
+impl<I: Iterator> Iterator for Chunks<I> {
+    type Item = Vec<I::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<_> = self.source.by_ref().take(self.size).collect();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Saga;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn double(chunk: Vec<u8>) -> Result<Vec<u8>, Failure<Vec<u8>, &'static str>> {
+        if chunk.contains(&0) {
+            Err(Failure::new(chunk, "zero"))
+        } else {
+            Ok(chunk.into_iter().map(|b| b * 2).collect())
+        }
+    }
+    fn halve(chunk: Vec<u8>) -> Vec<u8> {
+        chunk.into_iter().map(|b| b / 2).collect()
+    }
+    #[test]
+    fn tells_chunks() {
+        let saga = Saga::new(vec![Adventure::new(double, halve)]).chunked(2);
+        let res = saga.tell(vec![1, 2, 3, 4, 5]);
+        assert_eq!(res.ok(), Some(vec![vec![2, 4], vec![6, 8], vec![10]]));
+    }
+    #[test]
+    fn reverts_chunks() {
+        let pulled = Rc::new(Cell::new(0));
+        let count = pulled.clone();
+        let source = vec![2, 4, 6, 0, 8, 10]
+            .into_iter()
+            .inspect(move |_| count.set(count.get() + 1));
+        let saga = Saga::new(vec![Adventure::new(double, halve)]).chunked(2);
+        match saga.tell(source) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, vec![vec![2, 4], vec![3, 0]]);
+            }
+        }
+        assert_eq!(pulled.get(), 4);
+    }
+}
@@ -0,0 +1,263 @@
+//! Sagas whose steps are handed a shared context next to the accumulator.
+//!
+//! Request scoped data like trace ids or tenant information often has to
+//! reach every step without being part of the accumulator. A context saga
+//! borrows such a context for the time it is told and hands it to each
+//! forward and backward step as their first argument. Steps only ever
+//! see a shared reference so they can not change the context.
+//!
+//! Resources that live as long as the saga itself, like a configuration
+//! or a client, can be handed to a saga once with `Saga::new_with_shared`
+//! instead of capturing them in every step.
+
+use crate::adventure::{Forwarded, Reverted, Token, Told};
+use crate::hooks::Unhooked;
+use crate::saga::tell_;
+use crate::{Failure, Saga};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+type ContextForward<C, T, E> = Arc<dyn Fn(&C, T) -> Result<T, Failure<T, E>> + Send + Sync>;
+type ContextBackward<C, T> = Arc<dyn Fn(&C, T) -> T + Send + Sync>;
+
+/// An adventure whose steps are handed the context of the saga.
+pub struct ContextAdventure<C, T, E = Box<dyn Error>> {
+    forward: ContextForward<C, T, E>,
+    backward: ContextBackward<C, T>,
+}
+
+impl<C, T, E> ContextAdventure<C, T, E> {
+    /// Creates a new adventure from a forward and backward step that
+    /// both take the context as their first argument
+    pub fn new<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(&C, T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(&C, T) -> T + Send + Sync + 'static,
+    {
+        ContextAdventure {
+            forward: Arc::new(forward),
+            backward: Arc::new(backward),
+        }
+    }
+}
+
+impl<C, T, E> Clone for ContextAdventure<C, T, E> {
+    fn clone(&self) -> Self {
+        ContextAdventure {
+            forward: self.forward.clone(),
+            backward: self.backward.clone(),
+        }
+    }
+}
+
+impl<C, T, E> fmt::Debug for ContextAdventure<C, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ContextAdventure").finish_non_exhaustive()
+    }
+}
+
+/// A saga of adventures that share a context while it is told.
+pub struct ContextSaga<C, T, E = Box<dyn Error>> {
+    adventures: Vec<ContextAdventure<C, T, E>>,
+}
+
+impl<T, E> Saga<T, E> {
+    /// Creates a saga whose adventures are handed a context, it is
+    /// passed in when the saga is told
+    pub fn with_context<C>(adventures: Vec<ContextAdventure<C, T, E>>) -> ContextSaga<C, T, E> {
+        ContextSaga { adventures }
+    }
+}
+
+/// A saga that hands the same shared resource to all of its steps.
+pub struct SharedSaga<S, T, E = Box<dyn Error>> {
+    saga: ContextSaga<S, T, E>,
+    shared: Arc<S>,
+}
+
+impl<T, E> Saga<T, E> {
+    /// Creates a saga whose adventures are handed `shared` every time
+    /// the saga is told.
+    ///
+    /// The forward steps have the signature
+    /// `fn(&S, T) -> Result<T, Failure<T, E>>` and the backward steps
+    /// `fn(&S, T) -> T`.
+    pub fn new_with_shared<S>(
+        adventures: Vec<ContextAdventure<S, T, E>>,
+        shared: Arc<S>,
+    ) -> SharedSaga<S, T, E> {
+        SharedSaga {
+            saga: Saga::with_context(adventures),
+            shared,
+        }
+    }
+}
+
+impl<S, T, E> SharedSaga<S, T, E> {
+    /// The resource shared by all steps
+    pub fn shared(&self) -> &Arc<S> {
+        &self.shared
+    }
+    /// The number of adventures in the saga
+    pub fn len(&self) -> usize {
+        self.saga.len()
+    }
+    /// Whether the saga has no adventures
+    pub fn is_empty(&self) -> bool {
+        self.saga.is_empty()
+    }
+    /// Tells the saga handing the shared resource to every step, reverts
+    /// on failure and returns either the result or error
+    pub fn tell(&self, acc: T) -> Result<T, Failure<T, E>> {
+        self.saga.tell_with_context(&self.shared, acc)
+    }
+}
+
+impl<S, T, E> Clone for SharedSaga<S, T, E> {
+    fn clone(&self) -> Self {
+        SharedSaga {
+            saga: self.saga.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, T, E> fmt::Debug for SharedSaga<S, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedSaga")
+            .field("saga", &self.saga)
+            .field("shared", &self.shared)
+            .finish()
+    }
+}
+
+impl<C, T, E> ContextSaga<C, T, E> {
+    /// The number of adventures in the saga
+    pub fn len(&self) -> usize {
+        self.adventures.len()
+    }
+    /// Whether the saga has no adventures
+    pub fn is_empty(&self) -> bool {
+        self.adventures.is_empty()
+    }
+    /// Tells the saga handing `ctx` to every step, reverts on failure
+    /// and returns either the result or error
+    pub fn tell_with_context(&self, ctx: &C, acc: T) -> Result<T, Failure<T, E>> {
+        let steps = self
+            .adventures
+            .iter()
+            .map(|adventure| InContext { adventure, ctx });
+        tell_(steps, &mut Unhooked, acc)
+    }
+}
+
+impl<C, T, E> Clone for ContextSaga<C, T, E> {
+    fn clone(&self) -> Self {
+        ContextSaga {
+            adventures: self.adventures.clone(),
+        }
+    }
+}
+
+impl<C, T, E> fmt::Debug for ContextSaga<C, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ContextSaga")
+            .field("adventures", &self.adventures)
+            .finish()
+    }
+}
+
+/// An adventure paired with the context it is told in.
+struct InContext<'a, C, T, E> {
+    adventure: &'a ContextAdventure<C, T, E>,
+    ctx: &'a C,
+}
+
+impl<C, T, E> Told<T, E> for InContext<'_, C, T, E> {
+    fn applies(&self, _acc: &T) -> bool {
+        true
+    }
+    fn priority(&self) -> i32 {
+        0
+    }
+    fn forward(&mut self, acc: T) -> Forwarded<T, E> {
+        (self.adventure.forward)(self.ctx, acc).map(|acc| (acc, None))
+    }
+    fn backward(&mut self, acc: T, _token: Option<Token>, _error: &E) -> Reverted<T, E> {
+        Ok(((self.adventure.backward)(self.ctx, acc), Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ContextAdventure;
+    use crate::ContextSaga;
+    use crate::Failure;
+    use crate::Saga;
+    use crate::SharedSaga;
+    use std::sync::Arc;
+
+    struct Tenant {
+        limit: i32,
+    }
+
+    fn add(tenant: &Tenant, i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        if i + 1 > tenant.limit {
+            Err(Failure::new(i + 1, "over the limit"))
+        } else {
+            Ok(i + 1)
+        }
+    }
+    fn sub(_tenant: &Tenant, i: i32) -> i32 {
+        i - 1
+    }
+    #[test]
+    fn hands_context() {
+        let saga = Saga::with_context(vec![
+            ContextAdventure::new(add, sub),
+            ContextAdventure::new(add, sub),
+        ]);
+        assert_eq!(
+            saga.tell_with_context(&Tenant { limit: 10 }, 0).ok(),
+            Some(2)
+        );
+    }
+    #[test]
+    fn reverts_with_context() {
+        let saga = Saga::with_context(vec![
+            ContextAdventure::new(add, sub),
+            ContextAdventure::new(add, sub),
+        ]);
+        match saga.tell_with_context(&Tenant { limit: 1 }, 0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, 0);
+            }
+        }
+    }
+    #[test]
+    fn hands_shared() {
+        let saga = Saga::new_with_shared(
+            vec![
+                ContextAdventure::new(add, sub),
+                ContextAdventure::new(add, sub),
+            ],
+            Arc::new(Tenant { limit: 1 }),
+        );
+        assert_eq!(saga.shared().limit, 1);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 0),
+        }
+    }
+    #[test]
+    fn send_sync_sage() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<ContextSaga<Tenant, i32>>();
+        assert_send_sync::<SharedSaga<Tenant, i32>>();
+    }
+}
@@ -0,0 +1,145 @@
+//! Logs of the effects a saga had.
+//!
+//! For audits every forward and backward step that ran is appended to
+//! a log the caller owns, so the same log can be handed to several
+//! sagas. On failure the log holds the forward steps followed by the
+//! backward steps of the rollback in the order they ran.
+
+use crate::hooks::{Hooks, Limited};
+use crate::saga::tell_;
+use crate::{Adventure, Failure, Saga};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The direction a step was run in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The forward step of an adventure
+    Forward,
+    /// The backward step of an adventure
+    Backward,
+}
+
+/// A forward or backward step that ran while a saga was told.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectRecord {
+    index: usize,
+    name: Option<String>,
+    direction: Direction,
+    succeeded: bool,
+}
+
+impl EffectRecord {
+    /// The index of the adventure
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// The name of the adventure if it has one
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// Whether the forward or backward step was run
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+    /// Whether the step succeeded, for forward steps after all retries
+    pub fn succeeded(&self) -> bool {
+        self.succeeded
+    }
+}
+
+/// Appends every step that ran to the log.
+struct Logging<'a> {
+    log: &'a mut Vec<EffectRecord>,
+    names: Vec<Option<&'a str>>,
+}
+
+impl Logging<'_> {
+    fn record(&mut self, index: usize, direction: Direction, succeeded: bool) {
+        let name = self.names.get(index).copied().flatten().map(String::from);
+        self.log.push(EffectRecord {
+            index,
+            name,
+            direction,
+            succeeded,
+        });
+    }
+}
+
+impl<T, E> Hooks<T, E> for Logging<'_> {
+    fn after_forward(&mut self, index: usize, _acc: &T) {
+        self.record(index, Direction::Forward, true);
+    }
+    fn on_forward_failure(&mut self, index: usize, _error: &E) {
+        self.record(index, Direction::Forward, false);
+    }
+    fn after_backward(&mut self, index: usize, _acc: &T) {
+        self.record(index, Direction::Backward, true);
+    }
+    fn on_backward_failure(&mut self, index: usize, _error: &E) {
+        self.record(index, Direction::Backward, false);
+    }
+}
+
+impl<T, E> Saga<T, E> {
+    /// Tells a saga like `tell` and appends a record of every forward
+    /// and backward step that ran to `log`.
+    ///
+    /// Entries already in the log are kept. Adventures that were skipped
+    /// or already committed run no step and leave no record, the steps
+    /// of nested sagas are logged as the single step of their adventure.
+    pub fn tell_logging(&self, acc: T, log: &mut Vec<EffectRecord>) -> Result<T, Failure<T, E>> {
+        let mut logging = Logging {
+            log,
+            names: self.adventures.iter().map(Adventure::name).collect(),
+        };
+        tell_(&self.adventures, &mut Limited::new(&mut logging, &self.settings), acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc, inc2};
+    use crate::Adventure;
+    use crate::Direction;
+    use crate::Direction::*;
+    use crate::EffectRecord;
+    use crate::Saga;
+
+    fn steps(log: &[EffectRecord]) -> Vec<(usize, Direction, bool)> {
+        log.iter().map(|r| (r.index(), r.direction(), r.succeeded())).collect()
+    }
+    #[test]
+    fn logs_completed() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec).with_name("first"), Adventure::new(inc2, dec)]);
+        let mut log = Vec::new();
+        assert_eq!(saga.tell_logging(0, &mut log).ok(), Some(2));
+        assert_eq!(steps(&log), vec![(0, Forward, true), (1, Forward, true)]);
+        assert_eq!(log[0].name(), Some("first"));
+        assert_eq!(log[1].name(), None);
+    }
+    #[test]
+    fn logs_rollback() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut log = Vec::new();
+        assert!(saga.tell_logging(0, &mut log).is_err());
+        assert_eq!(
+            steps(&log),
+            vec![
+                (0, Forward, true),
+                (1, Forward, true),
+                (2, Forward, false),
+                (2, Backward, true),
+                (1, Backward, true),
+                (0, Backward, true),
+            ]
+        );
+        // the log is appended to
+        assert!(saga.tell_logging(0, &mut log).is_err());
+        assert_eq!(log.len(), 12);
+    }
+}
@@ -0,0 +1,159 @@
+//! Errors raised by the saga machinery itself.
+//!
+//! Sagas are generic over their error type, so every error the library
+//! raises on its own is a separate type that is turned into the error
+//! type of the saga with `From`. For the default `Box<dyn Error>` this
+//! works out of the box.
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use core::any::Any;
+use core::error::Error;
+use core::fmt;
+use core::time::Duration;
+
+/// A forward step did not finish within its timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut {
+    pub(crate) timeout: Duration,
+}
+
+impl TimedOut {
+    /// The timeout that was exceeded
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step timed out after {:?}", self.timeout)
+    }
+}
+
+impl Error for TimedOut {}
+
+/// A saga was cancelled before all adventures were told.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "saga was cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// A saga did not tell all adventures before its deadline.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineExceeded;
+
+#[cfg(feature = "std")]
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "saga exceeded its deadline")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DeadlineExceeded {}
+
+/// A saga told more forward steps than its limit allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepLimitExceeded {
+    pub(crate) limit: usize,
+}
+
+impl StepLimitExceeded {
+    /// The number of forward steps the saga was allowed to tell
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for StepLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "saga exceeded its limit of {} steps", self.limit)
+    }
+}
+
+impl Error for StepLimitExceeded {}
+
+/// A saga was rolled back past an irreversible adventure, its effect
+/// is still present and has to be undone by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrreversibleStepRolledBack;
+
+impl fmt::Display for IrreversibleStepRolledBack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an irreversible step was rolled back and needs manual intervention")
+    }
+}
+
+impl Error for IrreversibleStepRolledBack {}
+
+/// The error backward steps are handed while `Saga::validate_invertible`
+/// checks that they revert their forward steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InverseCheck;
+
+impl fmt::Display for InverseCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step reverted to check that it inverts its forward step")
+    }
+}
+
+impl Error for InverseCheck {}
+
+/// A strict saga without adventures was told.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptySaga;
+
+impl fmt::Display for EmptySaga {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "saga has no adventures")
+    }
+}
+
+impl Error for EmptySaga {}
+
+/// A step panicked, the panic was caught and turned into an
+/// error.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panicked {
+    pub(crate) message: Option<String>,
+}
+
+#[cfg(feature = "std")]
+impl Panicked {
+    pub(crate) fn new(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => Some(*message),
+            Err(payload) => payload.downcast_ref::<&str>().map(|m| String::from(*m)),
+        };
+        Panicked { message }
+    }
+    /// The message the step panicked with if it was a string
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "step panicked: {}", message),
+            None => write!(f, "step panicked"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for Panicked {}
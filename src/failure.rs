@@ -0,0 +1,334 @@
+//! Failures of forward and backward steps.
+
+use crate::CompArtifact;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+/// A simple failure that can return an error along with the new state.
+pub struct Failure<T, E = Box<dyn Error>> {
+    pub(crate) error: E,
+    pub(crate) state: T,
+    pub(crate) failed_at: usize,
+    pub(crate) origin: Option<Box<Origin>>,
+    pub(crate) compensations: Vec<CompensationFailure<E>>,
+    pub(crate) aborted_at: Option<usize>,
+    pub(crate) rolled_back: usize,
+    /// Boxed as they are usually absent, this keeps failures small
+    pub(crate) details: Option<Box<Details>>,
+}
+
+/// What is only recorded for some failures.
+#[derive(Default)]
+pub(crate) struct Details {
+    pub(crate) artifacts: Vec<CompArtifact>,
+    pub(crate) attempts: Vec<(usize, u32)>,
+}
+
+impl<T, E> Failure<T, E> {
+    /// Creates a new failure from a state and an error
+    pub fn new(state: T, error: E) -> Self {
+        Failure {
+            state,
+            error,
+            failed_at: 0,
+            origin: None,
+            compensations: Vec::new(),
+            aborted_at: None,
+            rolled_back: 0,
+            details: None,
+        }
+    }
+    /// Creates a new failure converting the error into `E`, for the
+    /// default error type this boxes any error
+    pub fn from_error<X>(state: T, error: X) -> Self
+    where
+        E: From<X>,
+    {
+        Failure::new(state, E::from(error))
+    }
+    /// The index of the adventure whose forward step failed
+    pub fn failed_at(&self) -> usize {
+        self.failed_at
+    }
+    /// The name of the adventure whose forward step failed if it was
+    /// given one
+    pub fn step_name(&self) -> Option<&str> {
+        self.origin.as_ref().and_then(|origin| origin.step.as_deref())
+    }
+    /// The phase of the adventure whose forward step failed if it was
+    /// grouped under one with `Saga::phase`
+    pub fn phase(&self) -> Option<&str> {
+        self.origin.as_ref().and_then(|origin| origin.phase.as_deref())
+    }
+    /// Truns the Failure into an Err
+    pub fn to_error(self) -> E {
+        self.error
+    }
+    /// The error that caused the failure
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+    /// The state the failure holds, for a saga this is the accumulator
+    /// after the rollback
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+    /// Turns the failure into its state
+    pub fn into_acc(self) -> T {
+        self.state
+    }
+    /// Turns the failure into its error and state
+    pub fn into_parts(self) -> (E, T) {
+        (self.error, self.state)
+    }
+    /// Turns the state of the failure into another one, everything else
+    /// is kept
+    pub(crate) fn map_state<U, F>(self, f: F) -> Failure<U, E>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Failure {
+            error: self.error,
+            state: f(self.state),
+            failed_at: self.failed_at,
+            origin: self.origin,
+            compensations: self.compensations,
+            aborted_at: self.aborted_at,
+            rolled_back: self.rolled_back,
+            details: self.details,
+        }
+    }
+    /// The index of the adventure at which an observer aborted the
+    /// rollback, neither it nor the adventures before it were reverted
+    pub fn aborted_at(&self) -> Option<usize> {
+        self.aborted_at
+    }
+    /// The number of backward steps that succeeded during the rollback,
+    /// when every adventure up to and including the failed one was
+    /// reverted this is `failed_at + 1`
+    pub fn rolled_back(&self) -> usize {
+        self.rolled_back
+    }
+    /// Returns the failed compensation if the rollback itself failed,
+    /// in that case the saga was only partially reverted. For a best
+    /// effort rollback this is the first compensation that failed.
+    pub fn compensation_failure(&self) -> Option<&CompensationFailure<E>> {
+        self.compensations.first()
+    }
+    /// All compensations that failed in the order they ran, a rollback
+    /// stops at the first one unless it was told with
+    /// `Saga::tell_best_effort`
+    pub fn compensation_failures(&self) -> &[CompensationFailure<E>] {
+        &self.compensations
+    }
+    /// The artifacts the backward steps created during the rollback in
+    /// the order they ran, see `Adventure::with_artifacts`
+    pub fn artifacts(&self) -> &[CompArtifact] {
+        self.details.as_ref().map_or(&[], |details| &details.artifacts)
+    }
+    /// How often each forward step was attempted as pairs of the index
+    /// of the adventure and the number of attempts, it is only recorded
+    /// when the saga was told with `Saga::tell_with_attempts`
+    pub fn attempts(&self) -> &[(usize, u32)] {
+        self.details.as_ref().map_or(&[], |details| &details.attempts)
+    }
+    /// The details of the failure, they are created once they are needed
+    pub(crate) fn details_mut(&mut self) -> &mut Details {
+        self.details.get_or_insert_with(Box::default)
+    }
+}
+
+/// The name and phase of the adventure a failure happened at, they are
+/// kept behind a single box so failures stay small.
+pub(crate) struct Origin {
+    pub(crate) step: Option<Box<str>>,
+    pub(crate) phase: Option<Box<str>>,
+}
+
+impl Origin {
+    /// The origin of a failure, `None` when the adventure has neither a
+    /// name nor a phase
+    pub(crate) fn new(step: Option<&str>, phase: Option<&str>) -> Option<Box<Self>> {
+        if step.is_none() && phase.is_none() {
+            return None;
+        }
+        Some(Box::new(Origin {
+            step: step.map(Box::from),
+            phase: phase.map(Box::from),
+        }))
+    }
+}
+
+/// Turns errors into failures so steps can use the `?` operator.
+pub trait ResultExt<V, X> {
+    /// Turns an error into a failure with the state returned by
+    /// `state`, which is only called when there is an error
+    fn or_fail<T, E, S>(self, state: S) -> Result<V, Failure<T, E>>
+    where
+        E: From<X>,
+        S: FnOnce() -> T;
+    /// Turns an error into a failure with `state` like `or_fail`, for
+    /// states that are cheap to hand over even when there is no error
+    fn or_fail_state<T, E>(self, state: T) -> Result<V, Failure<T, E>>
+    where
+        E: From<X>;
+}
+
+impl<V, X> ResultExt<V, X> for Result<V, X> {
+    fn or_fail<T, E, S>(self, state: S) -> Result<V, Failure<T, E>>
+    where
+        E: From<X>,
+        S: FnOnce() -> T,
+    {
+        self.map_err(|error| Failure::from_error(state(), error))
+    }
+    fn or_fail_state<T, E>(self, state: T) -> Result<V, Failure<T, E>>
+    where
+        E: From<X>,
+    {
+        self.map_err(|error| Failure::from_error(state, error))
+    }
+}
+
+impl<T: fmt::Debug, E: fmt::Display> fmt::Debug for Failure<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Failure")
+            .field("error", &format_args!("{}", self.error))
+            .field("state", &self.state)
+            .field("failed_at", &self.failed_at)
+            .field("step", &self.step_name())
+            .field("phase", &self.phase())
+            .field("compensations", &self.compensations)
+            .field("aborted_at", &self.aborted_at)
+            .field("rolled_back", &self.rolled_back)
+            .field("artifacts", &self.artifacts())
+            .field("attempts", &self.attempts())
+            .finish()
+    }
+}
+
+/// Displays which step failed, the error that caused the failure is its
+/// source.
+impl<T, E> fmt::Display for Failure<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.step_name() {
+            Some(step) => write!(f, "saga failed at step '{}'", step),
+            None => write!(f, "saga failed at step {}", self.failed_at),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for Failure<T, Box<dyn Error>> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+impl<T: fmt::Debug> Error for Failure<T, Box<dyn Error + Send + Sync>> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// The error of a failed saga along with the step that failed, for
+/// reporting it to operators.
+///
+/// It displays as `saga failed at step 'charge_card': <error>`, steps
+/// without a name are displayed by their index instead.
+#[derive(Debug)]
+pub struct SagaError<E = Box<dyn Error>> {
+    step: Option<Box<str>>,
+    failed_at: usize,
+    error: E,
+}
+
+impl<E> SagaError<E> {
+    /// The name of the adventure whose forward step failed if it was
+    /// given one
+    pub fn step_name(&self) -> Option<&str> {
+        self.step.as_deref()
+    }
+    /// The index of the adventure whose forward step failed
+    pub fn failed_at(&self) -> usize {
+        self.failed_at
+    }
+    /// The error that caused the saga to fail
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+    /// Turns the saga error into the error that caused it
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+impl<T, E> From<Failure<T, E>> for SagaError<E> {
+    fn from(failure: Failure<T, E>) -> Self {
+        SagaError {
+            step: failure.origin.and_then(|origin| origin.step),
+            failed_at: failure.failed_at,
+            error: failure.error,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for SagaError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.step {
+            Some(step) => write!(f, "saga failed at step '{}': {}", step, self.error),
+            None => write!(f, "saga failed at step {}: {}", self.failed_at, self.error),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for SagaError<E> {}
+
+/// A backward step that failed while reverting a saga.
+pub struct CompensationFailure<E = Box<dyn Error>> {
+    pub(crate) index: usize,
+    pub(crate) phase: Option<Box<str>>,
+    pub(crate) error: E,
+    /// Whether the adventure was irreversible, so the rollback went on
+    pub(crate) irreversible: bool,
+}
+
+impl<E> CompensationFailure<E> {
+    /// The failed compensation a rollback is reported by: irreversible
+    /// adventures do not stop the rollback, so the first backward step
+    /// that really failed is reported over them
+    pub(crate) fn reported(compensations: Vec<Self>) -> Option<Self> {
+        let failed = compensations
+            .iter()
+            .position(|compensation| !compensation.irreversible)
+            .unwrap_or_default();
+        compensations.into_iter().nth(failed)
+    }
+    /// The index of the adventure whose backward step failed, unless
+    /// the rollback was best effort it stalled there and none of the
+    /// adventures that would have been compensated after it were
+    /// reverted
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// The phase of the adventure whose backward step failed if it was
+    /// grouped under one
+    pub fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+    /// The error returned by the failed backward step
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Debug for CompensationFailure<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompensationFailure")
+            .field("index", &self.index)
+            .field("phase", &self.phase)
+            .field("error", &format_args!("{}", self.error))
+            .finish()
+    }
+}
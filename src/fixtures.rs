@@ -0,0 +1,54 @@
+//! Steps and errors shared by the tests of all modules.
+
+use crate::Failure;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub(crate) struct StupidError {
+    pub(crate) stupid: bool,
+}
+
+impl Error for StupidError {
+    fn description(&self) -> &str {
+        "stupid error"
+    }
+}
+impl fmt::Display for StupidError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "is stupid: {}", self.stupid)
+    }
+}
+/// Fails once the accumulator reached 2
+pub(crate) fn inc2(i: i32) -> Result<i32, Failure<i32>> {
+    if i >= 2 {
+        Err(Failure::new(i + 1, Box::new(StupidError { stupid: true })))
+    } else {
+        Ok(i + 1)
+    }
+}
+pub(crate) fn inc(i: i32) -> Result<i32, Failure<i32>> {
+    Ok(i + 1)
+}
+pub(crate) fn dec(i: i32) -> i32 {
+    i - 1
+}
+
+/// The same steps failing with a plain message instead of a boxed
+/// error.
+pub(crate) mod plain {
+    pub(crate) use super::dec;
+    use crate::Failure;
+
+    /// Fails once the accumulator reached 2
+    pub(crate) fn inc2(i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        if i >= 2 {
+            Err(Failure::new(i + 1, "too big"))
+        } else {
+            Ok(i + 1)
+        }
+    }
+    pub(crate) fn inc(i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        Ok(i + 1)
+    }
+}
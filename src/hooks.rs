@@ -0,0 +1,401 @@
+//! Internal hooks into telling a saga.
+//!
+//! The public `Observer` only sees indices, the hooks in here also see
+//! the accumulator and are used to build the richer ways of telling a
+//! saga on top of the same machinery.
+
+use crate::{AbortRollback, Cancelled, EmptySaga, Observer, RollbackOrder, StepLimitExceeded};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Hooks called by the saga machinery at every step.
+pub(crate) trait Hooks<T, E> {
+    fn before_forward(&mut self, _index: usize, _acc: &T) {}
+    fn after_forward(&mut self, _index: usize, _acc: &T) {}
+    /// Called after a forward step ran with the number of attempts it
+    /// took, whether it succeeded or not
+    fn attempted(&mut self, _index: usize, _attempts: u32) {}
+    fn on_forward_failure(&mut self, _index: usize, _error: &E) {}
+    fn before_backward(&mut self, _index: usize) -> Result<(), AbortRollback> {
+        Ok(())
+    }
+    fn after_backward(&mut self, _index: usize, _acc: &T) {}
+    fn on_backward_failure(&mut self, _index: usize, _error: &E) {}
+    /// Whether the rollback goes on past failing backward steps
+    fn best_effort(&self) -> bool {
+        false
+    }
+    /// The error to roll back with when the saga was cancelled before
+    /// the next forward step
+    fn cancelled(&mut self) -> Option<E> {
+        None
+    }
+    /// The error the forward step at `index` is made to fail with
+    /// instead of running it
+    fn injected(&mut self, _index: usize) -> Option<E> {
+        None
+    }
+    /// Called once a rollback is over with the index of the failed
+    /// adventure and the number of backward steps that succeeded
+    fn after_rollback(&mut self, _failed_at: usize, _error: &E, _reverted: usize) {}
+    /// Whether the rollback leaves the adventure at `index` as it is
+    fn keeps(&self, _index: usize) -> bool {
+        false
+    }
+    /// The error to fail with when the saga has no adventures
+    fn empty(&mut self) -> Option<E> {
+        None
+    }
+    /// The order in which told steps of the same priority are reverted
+    fn rollback_order(&self) -> RollbackOrder {
+        RollbackOrder::Reverse
+    }
+    /// Hands the accumulator to the backward step of the adventure at
+    /// `index`, the hooks can swap it for another one
+    fn restore(&mut self, _index: usize, acc: T) -> T {
+        acc
+    }
+    /// The backward step of adventures that defer to the saga for it
+    fn default_backward(&self) -> Option<&dyn Fn(T) -> T> {
+        None
+    }
+    /// Captures the accumulator after a forward step succeeded, an error
+    /// rolls the saga back including that step
+    fn checkpoint(&mut self, _index: usize, _acc: &T) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Hooks that do nothing.
+pub(crate) struct Unhooked;
+
+impl<T, E> Hooks<T, E> for Unhooked {}
+
+/// Hooks that only hand out the default backward step of a saga, for
+/// rollbacks that ignore its other settings.
+pub(crate) struct Defaulted<'a, T>(pub(crate) Option<&'a dyn Fn(T) -> T>);
+
+impl<'a, T> Defaulted<'a, T> {
+    pub(crate) fn new<E>(settings: &'a Settings<T, E>) -> Self {
+        Defaulted(settings.default_backward.as_deref().map(|f| f as _))
+    }
+}
+
+impl<T, E> Hooks<T, E> for Defaulted<'_, T> {
+    fn default_backward(&self) -> Option<&dyn Fn(T) -> T> {
+        self.0
+    }
+}
+
+/// Hooks that only ask for a best effort rollback.
+pub(crate) struct BestEffort;
+
+impl<T, E> Hooks<T, E> for BestEffort {
+    fn best_effort(&self) -> bool {
+        true
+    }
+}
+
+/// Hooks that cancel the saga once the flag is set.
+pub(crate) struct Cancellation<'a>(pub(crate) &'a AtomicBool);
+
+impl<T, E> Hooks<T, E> for Cancellation<'_>
+where
+    E: From<Cancelled>,
+{
+    fn cancelled(&mut self) -> Option<E> {
+        if self.0.load(Ordering::Acquire) {
+            Some(E::from(Cancelled))
+        } else {
+            None
+        }
+    }
+}
+
+/// Hooks that hand the accumulator to a callback after each forward
+/// step so it can be persisted.
+pub(crate) struct Persisted<F>(pub(crate) F);
+
+impl<T, E, F> Hooks<T, E> for Persisted<F>
+where
+    F: FnMut(usize, &T) -> Result<(), Box<dyn Error>>,
+    E: From<Box<dyn Error>>,
+{
+    fn checkpoint(&mut self, index: usize, acc: &T) -> Result<(), E> {
+        (self.0)(index, acc).map_err(E::from)
+    }
+}
+
+/// Hooks that hand the accumulator to a callback after each forward
+/// step so it can be inspected.
+pub(crate) struct Inspected<'a, T>(pub(crate) Option<Inspect<'a, T>>);
+
+/// Called with the index of each adventure and the accumulator it
+/// returned.
+pub(crate) type Inspect<'a, T> = &'a dyn Fn(usize, &T);
+
+impl<T, E> Hooks<T, E> for Inspected<'_, T> {
+    fn after_forward(&mut self, index: usize, acc: &T) {
+        if let Some(inspect) = self.0 {
+            inspect(index, acc);
+        }
+    }
+}
+
+/// Hooks that record how often each forward step was attempted.
+pub(crate) struct Attempts(pub(crate) Vec<(usize, u32)>);
+
+impl<T, E> Hooks<T, E> for Attempts {
+    fn attempted(&mut self, index: usize, attempts: u32) {
+        self.0.push((index, attempts));
+    }
+}
+
+/// Hooks that snapshot the accumulator after each forward step and hand
+/// the snapshot to the backward step of the same adventure.
+pub(crate) struct Snapshots<T>(BTreeMap<usize, T>);
+
+impl<T> Snapshots<T> {
+    pub(crate) fn new() -> Self {
+        Snapshots(BTreeMap::new())
+    }
+}
+
+impl<T: Clone, E> Hooks<T, E> for Snapshots<T> {
+    fn after_forward(&mut self, index: usize, acc: &T) {
+        self.0.insert(index, acc.clone());
+    }
+    fn restore(&mut self, index: usize, acc: T) -> T {
+        self.0.remove(&index).unwrap_or(acc)
+    }
+}
+
+/// The number of forward steps a saga may tell.
+pub(crate) struct StepLimit<E> {
+    pub(crate) max: usize,
+    pub(crate) error: fn(StepLimitExceeded) -> E,
+}
+
+impl<E> Clone for StepLimit<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for StepLimit<E> {}
+
+/// Turns telling a saga without adventures into an error.
+pub(crate) type Strict<E> = fn(EmptySaga) -> E;
+
+/// Called after any rollback of a saga with the index of the failed
+/// adventure, the error and the number of compensated adventures.
+pub(crate) type OnRollback<E> = Arc<dyn Fn(usize, &E, usize) + Send + Sync>;
+
+/// Reverts the adventures created with `Adventure::forward_only`.
+pub(crate) type DefaultBackward<T> = Arc<dyn Fn(T) -> T + Send + Sync>;
+
+/// The settings of a saga that apply however it is told.
+pub(crate) struct Settings<T, E> {
+    pub(crate) limit: Option<StepLimit<E>>,
+    pub(crate) strict: Option<Strict<E>>,
+    pub(crate) on_rollback: Option<OnRollback<E>>,
+    pub(crate) order: RollbackOrder,
+    pub(crate) default_backward: Option<DefaultBackward<T>>,
+}
+
+impl<T, E> Settings<T, E> {
+    pub(crate) const fn new() -> Self {
+        Settings {
+            limit: None,
+            strict: None,
+            on_rollback: None,
+            order: RollbackOrder::Reverse,
+            default_backward: None,
+        }
+    }
+}
+
+impl<T, E> Settings<T, E> {
+    /// The settings for telling the saga on a borrowed accumulator, the
+    /// default backward step takes it by value so it is left out
+    pub(crate) fn borrowed<'a>(&self) -> Settings<&'a mut T, E> {
+        Settings {
+            limit: self.limit,
+            strict: self.strict,
+            on_rollback: self.on_rollback.clone(),
+            order: self.order,
+            default_backward: None,
+        }
+    }
+}
+
+impl<T, E> Clone for Settings<T, E> {
+    fn clone(&self) -> Self {
+        Settings {
+            limit: self.limit,
+            strict: self.strict,
+            on_rollback: self.on_rollback.clone(),
+            order: self.order,
+            default_backward: self.default_backward.clone(),
+        }
+    }
+}
+
+/// Wraps other hooks and applies the settings of the saga: it stops
+/// the saga once it told as many forward steps as the limit allows, or
+/// before it starts when it is strict and has no adventures, and reports
+/// every rollback.
+pub(crate) struct Limited<'a, H: ?Sized, T, E> {
+    hooks: &'a mut H,
+    settings: &'a Settings<T, E>,
+    told: usize,
+}
+
+impl<'a, H: ?Sized, T, E> Limited<'a, H, T, E> {
+    pub(crate) fn new(hooks: &'a mut H, settings: &'a Settings<T, E>) -> Self {
+        Limited {
+            hooks,
+            settings,
+            told: 0,
+        }
+    }
+    /// Wraps the hooks for a saga that already told `told` forward steps
+    pub(crate) fn resumed(hooks: &'a mut H, settings: &'a Settings<T, E>, told: usize) -> Self {
+        Limited { hooks, settings, told }
+    }
+    /// The number of forward steps told so far
+    pub(crate) fn told(&self) -> usize {
+        self.told
+    }
+}
+
+impl<T, E, H> Hooks<T, E> for Limited<'_, H, T, E>
+where
+    H: Hooks<T, E> + ?Sized,
+{
+    fn before_forward(&mut self, index: usize, acc: &T) {
+        self.hooks.before_forward(index, acc);
+    }
+    fn after_forward(&mut self, index: usize, acc: &T) {
+        self.hooks.after_forward(index, acc);
+    }
+    fn attempted(&mut self, index: usize, attempts: u32) {
+        self.hooks.attempted(index, attempts);
+    }
+    fn on_forward_failure(&mut self, index: usize, error: &E) {
+        self.hooks.on_forward_failure(index, error);
+    }
+    fn before_backward(&mut self, index: usize) -> Result<(), AbortRollback> {
+        self.hooks.before_backward(index)
+    }
+    fn after_backward(&mut self, index: usize, acc: &T) {
+        self.hooks.after_backward(index, acc);
+    }
+    fn on_backward_failure(&mut self, index: usize, error: &E) {
+        self.hooks.on_backward_failure(index, error);
+    }
+    fn best_effort(&self) -> bool {
+        self.hooks.best_effort()
+    }
+    fn injected(&mut self, index: usize) -> Option<E> {
+        self.hooks.injected(index)
+    }
+    fn keeps(&self, index: usize) -> bool {
+        self.hooks.keeps(index)
+    }
+    fn after_rollback(&mut self, failed_at: usize, error: &E, reverted: usize) {
+        self.hooks.after_rollback(failed_at, error, reverted);
+        if let Some(on_rollback) = &self.settings.on_rollback {
+            on_rollback(failed_at, error, reverted);
+        }
+    }
+    fn empty(&mut self) -> Option<E> {
+        match self.settings.strict {
+            Some(error) => Some(error(EmptySaga)),
+            None => self.hooks.empty(),
+        }
+    }
+    fn checkpoint(&mut self, index: usize, acc: &T) -> Result<(), E> {
+        self.hooks.checkpoint(index, acc)
+    }
+    fn rollback_order(&self) -> RollbackOrder {
+        self.settings.order
+    }
+    fn restore(&mut self, index: usize, acc: T) -> T {
+        self.hooks.restore(index, acc)
+    }
+    fn default_backward(&self) -> Option<&dyn Fn(T) -> T> {
+        self.settings.default_backward.as_deref().map(|f| f as _)
+    }
+    fn cancelled(&mut self) -> Option<E> {
+        if let Some(error) = self.hooks.cancelled() {
+            return Some(error);
+        }
+        match self.settings.limit {
+            Some(limit) if self.told >= limit.max => {
+                Some((limit.error)(StepLimitExceeded { limit: limit.max }))
+            }
+            _ => {
+                self.told += 1;
+                None
+            }
+        }
+    }
+}
+
+/// Forwards the hooks to a public observer.
+pub(crate) struct Observed<'a, O: ?Sized> {
+    observer: &'a mut O,
+    /// The phase of each adventure
+    phases: Vec<Option<&'a str>>,
+    /// The phase of the last step that ran
+    phase: Option<&'a str>,
+}
+
+impl<'a, O: ?Sized> Observed<'a, O> {
+    pub(crate) fn new(observer: &'a mut O, phases: Vec<Option<&'a str>>) -> Self {
+        Observed {
+            observer,
+            phases,
+            phase: None,
+        }
+    }
+    fn enter<E>(&mut self, index: usize)
+    where
+        O: Observer<E>,
+    {
+        let phase = self.phases.get(index).copied().flatten();
+        if phase != self.phase {
+            self.phase = phase;
+            if let Some(phase) = phase {
+                self.observer.on_phase(index, phase);
+            }
+        }
+    }
+}
+
+impl<T, E, O> Hooks<T, E> for Observed<'_, O>
+where
+    O: Observer<E> + ?Sized,
+{
+    fn before_forward(&mut self, index: usize, _acc: &T) {
+        self.enter(index);
+        self.observer.on_forward_start(index);
+    }
+    fn after_forward(&mut self, index: usize, _acc: &T) {
+        self.observer.on_forward_success(index);
+    }
+    fn on_forward_failure(&mut self, index: usize, error: &E) {
+        self.observer.on_forward_failure(index, error);
+    }
+    fn before_backward(&mut self, index: usize) -> Result<(), AbortRollback> {
+        self.observer.before_backward(index)?;
+        self.enter(index);
+        self.observer.on_backward(index);
+        Ok(())
+    }
+}
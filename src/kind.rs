@@ -0,0 +1,315 @@
+//! Categories of failed sagas for telling the library's own failure
+//! modes apart from errors of the steps.
+
+use crate::{
+    Cancelled, CompensationFailure, EmptySaga, Failure, IrreversibleStepRolledBack, SagaOutcome,
+    SagaResult, StepLimitExceeded, TimedOut,
+};
+#[cfg(feature = "std")]
+use crate::{DeadlineExceeded, Panicked};
+use alloc::boxed::Box;
+use core::error::Error;
+
+/// What made a saga fail, told apart without matching on messages.
+///
+/// The error of a step stays boxed inside `Step` and next to the
+/// compensation error in `CompensationFailed`, errors the library raised
+/// itself are unboxed.
+#[derive(Debug)]
+pub enum SagaErrorKind {
+    /// A forward step failed with an error of its own, the saga was
+    /// rolled back
+    Step(Box<dyn Error>),
+    /// A forward step timed out, the saga was rolled back
+    TimedOut(TimedOut),
+    /// The saga was cancelled, the adventures told so far were rolled
+    /// back
+    Cancelled(Cancelled),
+    /// A forward step panicked, the saga was rolled back
+    #[cfg(feature = "std")]
+    Panicked(Panicked),
+    /// A strict saga without adventures was told
+    EmptySaga(EmptySaga),
+    /// The saga told more forward steps than its limit allows, the
+    /// adventures told so far were rolled back
+    StepLimitExceeded(StepLimitExceeded),
+    /// The saga passed its deadline, the adventures told so far were
+    /// rolled back
+    #[cfg(feature = "std")]
+    DeadlineExceeded(DeadlineExceeded),
+    /// A backward step failed so the saga was only partially rolled
+    /// back
+    CompensationFailed {
+        /// The error that caused the rollback
+        error: Box<dyn Error>,
+        /// The error of the first backward step that failed
+        compensation: Box<dyn Error>,
+        /// The index of the adventure whose backward step failed
+        index: usize,
+    },
+    /// The saga was rolled back past an irreversible adventure whose
+    /// effect has to be undone by hand, every other backward step ran
+    IrreversibleStepRolledBack {
+        /// The error that caused the rollback
+        error: Box<dyn Error>,
+        /// The index of the irreversible adventure
+        index: usize,
+    },
+    /// An observer aborted the rollback
+    RollbackAborted {
+        /// The error that caused the rollback
+        error: Box<dyn Error>,
+        /// The index at which the rollback was aborted
+        aborted_at: usize,
+    },
+}
+
+impl SagaErrorKind {
+    /// Sorts the error a rollback was started for
+    fn of_error(error: Box<dyn Error>) -> Self {
+        let error = match error.downcast::<TimedOut>() {
+            Ok(timed_out) => return SagaErrorKind::TimedOut(*timed_out),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<Cancelled>() {
+            Ok(cancelled) => return SagaErrorKind::Cancelled(*cancelled),
+            Err(error) => error,
+        };
+        #[cfg(feature = "std")]
+        let error = match error.downcast::<Panicked>() {
+            Ok(panicked) => return SagaErrorKind::Panicked(*panicked),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<EmptySaga>() {
+            Ok(empty) => return SagaErrorKind::EmptySaga(*empty),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<StepLimitExceeded>() {
+            Ok(exceeded) => return SagaErrorKind::StepLimitExceeded(*exceeded),
+            Err(error) => error,
+        };
+        #[cfg(feature = "std")]
+        let error = match error.downcast::<DeadlineExceeded>() {
+            Ok(exceeded) => return SagaErrorKind::DeadlineExceeded(*exceeded),
+            Err(error) => error,
+        };
+        SagaErrorKind::Step(error)
+    }
+    /// Sorts a rollback that reported a failed backward step
+    fn of_compensation(error: Box<dyn Error>, compensation: Box<dyn Error>, index: usize) -> Self {
+        if compensation.is::<IrreversibleStepRolledBack>() {
+            SagaErrorKind::IrreversibleStepRolledBack { error, index }
+        } else {
+            SagaErrorKind::CompensationFailed {
+                error,
+                compensation,
+                index,
+            }
+        }
+    }
+}
+
+impl<T> Failure<T> {
+    /// Sorts the failure into the kind of failure it is, the state is
+    /// dropped
+    pub fn into_kind(self) -> SagaErrorKind {
+        SagaErrorKind::from(self)
+    }
+}
+
+impl<T> SagaOutcome<T> {
+    /// Sorts the outcome into the kind of failure it is, `None` if every
+    /// adventure was told
+    pub fn into_kind(self) -> Option<SagaErrorKind> {
+        match self {
+            SagaOutcome::Completed(_) => None,
+            SagaOutcome::RolledBack { error, .. } => Some(SagaErrorKind::of_error(error)),
+            SagaOutcome::RollbackFailed {
+                original,
+                compensation_error,
+                stalled_at,
+                ..
+            } => Some(SagaErrorKind::of_compensation(
+                original,
+                compensation_error,
+                stalled_at,
+            )),
+            SagaOutcome::RollbackAborted {
+                error, aborted_at, ..
+            } => Some(SagaErrorKind::RollbackAborted { error, aborted_at }),
+        }
+    }
+}
+
+impl<T> SagaResult<T> {
+    /// Sorts the result into the kind of failure it is, `None` if the
+    /// saga committed
+    pub fn into_kind(self) -> Option<SagaErrorKind> {
+        self.into_result().err().map(SagaErrorKind::from)
+    }
+}
+
+impl<T> From<Failure<T>> for SagaErrorKind {
+    fn from(failure: Failure<T>) -> Self {
+        let Failure {
+            error,
+            compensations,
+            aborted_at,
+            ..
+        } = failure;
+        if let Some(compensation) = CompensationFailure::reported(compensations) {
+            return SagaErrorKind::of_compensation(error, compensation.error, compensation.index);
+        }
+        if let Some(aborted_at) = aborted_at {
+            return SagaErrorKind::RollbackAborted { error, aborted_at };
+        }
+        SagaErrorKind::of_error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc};
+    use crate::Adventure;
+    use crate::Cancelled;
+    use crate::Failure;
+    use crate::Saga;
+    use crate::SagaErrorKind;
+    use crate::SagaOutcome;
+    use crate::SagaResult;
+    use std::sync::atomic::AtomicBool;
+
+    fn fail(i: i32) -> Result<i32, Failure<i32>> {
+        Err(Failure::from_error(i, "failed"))
+    }
+    fn cancel(i: i32) -> Result<i32, Failure<i32>> {
+        Err(Failure::from_error(i, Cancelled))
+    }
+    #[test]
+    fn step() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(fail, dec)]);
+        match saga.tell(0).unwrap_err().into_kind() {
+            SagaErrorKind::Step(error) => assert_eq!(error.to_string(), "failed"),
+            _ => unimplemented!(),
+        }
+    }
+    #[test]
+    fn cancelled() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(cancel, dec)]);
+        assert!(matches!(
+            saga.tell(0).unwrap_err().into_kind(),
+            SagaErrorKind::Cancelled(_)
+        ));
+        let saga = Saga::new(vec![Adventure::new(inc, dec)]);
+        let res = saga.tell_cancellable(0, &AtomicBool::new(true));
+        assert!(matches!(
+            SagaErrorKind::from(res.unwrap_err()),
+            SagaErrorKind::Cancelled(_)
+        ));
+    }
+    #[test]
+    fn compensation_failed() {
+        let saga = Saga::new(vec![
+            Adventure::fallible(inc, fail),
+            Adventure::new(fail, dec),
+        ]);
+        match saga.tell(0).unwrap_err().into_kind() {
+            SagaErrorKind::CompensationFailed { index, .. } => assert_eq!(index, 0),
+            _ => unimplemented!(),
+        }
+    }
+    #[test]
+    fn empty_saga() {
+        let saga = Saga::<i32>::empty().strict();
+        assert!(matches!(
+            saga.tell(0).unwrap_err().into_kind(),
+            SagaErrorKind::EmptySaga(_)
+        ));
+    }
+    #[test]
+    fn step_limit_exceeded() {
+        let saga =
+            Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc, dec)]).with_max_steps(1);
+        match saga.tell(0).unwrap_err().into_kind() {
+            SagaErrorKind::StepLimitExceeded(exceeded) => assert_eq!(exceeded.limit(), 1),
+            _ => unimplemented!(),
+        }
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn deadline_exceeded() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec)]);
+        let res = saga.tell_deadline(0, std::time::Instant::now());
+        assert!(matches!(
+            res.unwrap_err().into_kind(),
+            SagaErrorKind::DeadlineExceeded(_)
+        ));
+    }
+    #[test]
+    fn irreversible_step_rolled_back() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::irreversible(inc),
+            Adventure::new(fail, dec),
+        ]);
+        match saga.tell(0).unwrap_err().into_kind() {
+            SagaErrorKind::IrreversibleStepRolledBack { error, index } => {
+                assert_eq!(error.to_string(), "failed");
+                assert_eq!(index, 1);
+            }
+            _ => unimplemented!(),
+        }
+        let saga = Saga::new(vec![
+            Adventure::fallible(inc, fail),
+            Adventure::irreversible(inc),
+            Adventure::new(fail, dec),
+        ]);
+        match saga.tell(0).unwrap_err().into_kind() {
+            SagaErrorKind::CompensationFailed { index, .. } => assert_eq!(index, 0),
+            _ => unimplemented!(),
+        }
+    }
+    #[test]
+    fn outcomes() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(cancel, dec)]);
+        assert!(matches!(
+            SagaOutcome::from(saga.tell(0)).into_kind(),
+            Some(SagaErrorKind::Cancelled(_))
+        ));
+        assert!(matches!(
+            SagaResult::from(saga.tell(0)).into_kind(),
+            Some(SagaErrorKind::Cancelled(_))
+        ));
+        let saga = Saga::new(vec![
+            Adventure::irreversible(inc),
+            Adventure::new(fail, dec),
+        ]);
+        assert!(matches!(
+            SagaOutcome::from(saga.tell(0)).into_kind(),
+            Some(SagaErrorKind::IrreversibleStepRolledBack { index: 0, .. })
+        ));
+        let saga = Saga::new(vec![
+            Adventure::fallible(inc, fail),
+            Adventure::irreversible(inc),
+            Adventure::new(fail, dec),
+        ]);
+        match SagaOutcome::from(saga.tell(0)) {
+            SagaOutcome::RollbackFailed {
+                compensation_error,
+                stalled_at,
+                ..
+            } => {
+                assert_eq!(compensation_error.to_string(), "failed");
+                assert_eq!(stalled_at, 0);
+            }
+            _ => unimplemented!(),
+        }
+        assert!(matches!(
+            SagaOutcome::from(saga.tell(0)).into_kind(),
+            Some(SagaErrorKind::CompensationFailed { index: 0, .. })
+        ));
+        let saga = Saga::new(vec![Adventure::new(inc, dec)]);
+        assert!(SagaOutcome::from(saga.tell(0)).into_kind().is_none());
+        assert!(SagaResult::from(saga.tell(0)).into_kind().is_none());
+    }
+}
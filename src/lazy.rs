@@ -0,0 +1,102 @@
+//! Sagas whose adventures are generated while the saga is told.
+//!
+//! A lazy saga pulls its adventures from an iterator one at a time so
+//! they do not all have to be created up front. Since a rollback has to
+//! walk the told adventures backwards every adventure that was told is
+//! kept in a buffer until the saga finishes, only the adventures that are
+//! never reached are saved. A saga that fails late thus holds about as
+//! many adventures as a `Saga` would.
+
+use crate::hooks::Unhooked;
+use crate::saga::tell_;
+use crate::{Adventure, Failure, Saga};
+use core::iter::FromIterator;
+
+/// A saga whose adventures are pulled from an iterator as it is told.
+pub struct LazySaga<I> {
+    adventures: I,
+}
+
+impl<T, E> Saga<T, E> {
+    /// Creates a saga that generates its adventures lazily, each one
+    /// is created right before it is told
+    pub fn lazy<I>(adventures: I) -> LazySaga<I::IntoIter>
+    where
+        I: IntoIterator<Item = Adventure<T, E>>,
+    {
+        LazySaga {
+            adventures: adventures.into_iter(),
+        }
+    }
+}
+
+impl<I, T, E> LazySaga<I>
+where
+    I: Iterator<Item = Adventure<T, E>>,
+{
+    /// Tells the saga consuming it, reverts on failure and returns
+    /// either the result or error
+    pub fn tell(self, acc: T) -> Result<T, Failure<T, E>> {
+        tell_(self.adventures, &mut Unhooked, acc)
+    }
+}
+
+impl<T, E> FromIterator<Adventure<T, E>> for Saga<T, E> {
+    fn from_iter<I: IntoIterator<Item = Adventure<T, E>>>(adventures: I) -> Self {
+        Saga::new(adventures.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc, StupidError};
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Saga;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn fail(i: i32) -> Result<i32, Failure<i32>> {
+        Err(Failure::new(i, Box::new(StupidError { stupid: true })))
+    }
+    #[test]
+    fn tells_lazily() {
+        let created = Rc::new(Cell::new(0));
+        let count = created.clone();
+        let adventures = (0..100_000).map(move |_| {
+            count.set(count.get() + 1);
+            Adventure::new(inc, dec)
+        });
+        match Saga::lazy(adventures).tell(0) {
+            Ok(res) => assert_eq!(res, 100_000),
+            Err(_) => unimplemented!(),
+        }
+        assert_eq!(created.get(), 100_000);
+    }
+    #[test]
+    fn reverts_buffered() {
+        let created = Rc::new(Cell::new(0));
+        let count = created.clone();
+        let adventures = (0..10).map(move |i| {
+            count.set(count.get() + 1);
+            if i == 5 {
+                Adventure::new(fail, dec)
+            } else {
+                Adventure::new(inc, dec)
+            }
+        });
+        match Saga::lazy(adventures).tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 5);
+                assert_eq!(failure.state, -1);
+            }
+        }
+        assert_eq!(created.get(), 6);
+    }
+    #[test]
+    fn collects() {
+        let saga: Saga<i32> = (0..3).map(|_| Adventure::new(inc, dec)).collect();
+        assert_eq!(saga.len(), 3);
+    }
+}
@@ -57,143 +57,1790 @@
 //!         }
 //!     }
 //! ```
+//!
+//! Without the default `std` feature the crate is `no_std` and only
+//! needs `alloc`, retries, timeouts, parallel stages, timings and
+//! catching panics are not available then.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(trivial_numeric_casts,
         missing_docs,
         unstable_features,
         unused_import_braces,
 )]
-use std::error::Error;
 
-/// A sage of many adventures that can be told.
-pub struct Saga<T> {
-    adventures: Vec<Adventure<T>>,
-}
+extern crate alloc;
 
-impl<T> Saga<T> {
-    /// Creates a new saga from a vector of adventures
-    pub fn new(adventures: Vec<Adventure<T>>) -> Self {
-        Saga { adventures: adventures }
-    }
-    /// Tells a saga, reverts on failure and returns either the result or error
-    pub fn tell(self: &Self, acc: T) -> Result<T, Failure<T>> {
-        tell_(&self.adventures, 0, acc)
-    }
-}
+mod macros;
+#[doc(hidden)]
+pub use crate::macros::__vec;
 
-/// An adventure that can can forward succeed or fail and be reverted.
-/// Make sure that a failure includes enough info for THIS step itsel
-/// to be reverted
-pub struct Adventure<T> {
-    forward: fn(T) -> Result<T, Failure<T>>,
-    backward: fn(T) -> T,
-}
+mod adventure;
+pub use crate::adventure::{Adventure, Compensations, Control};
 
-impl<T> Adventure<T> {
-    /// Creates a new adventure with a forward and backward step
-    pub fn new(forward: fn(T) -> Result<T, Failure<T>>, backward: fn(T) -> T) -> Self {
-        Adventure { forward, backward }
-    }
-    fn forward(self: &Adventure<T>, acc: T) -> Result<T, Failure<T>> {
-        let f = self.forward;
-        f(acc)
-    }
-    fn backward(self: &Adventure<T>, acc: T) -> T {
-        let f = self.backward;
-        f(acc)
-    }
-}
+mod error;
+pub use crate::error::{Cancelled, EmptySaga, InverseCheck, IrreversibleStepRolledBack, StepLimitExceeded, TimedOut};
+#[cfg(feature = "std")]
+pub use crate::error::{DeadlineExceeded, Panicked};
 
-/// A simple failure that can return an error along with the new state.
-pub struct Failure<T> {
-    error: Box<Error>,
-    state: T,
-}
+mod failure;
+pub use crate::failure::{CompensationFailure, Failure, ResultExt, SagaError};
 
-impl<T> Failure<T> {
-    /// Creates a new failure from a state and an error
-    pub fn new(state: T, error: Box<Error>) -> Self {
-        Failure { state, error }
-    }
-    /// Truns the Failure into an Err
-    pub fn to_error(self) -> Box<Error> {
-        self.error
-    }
-}
+mod warning;
+pub use crate::warning::Warning;
 
-fn tell_<T>(saga: &Vec<Adventure<T>>, i: usize, acc: T) -> Result<T, Failure<T>> {
-    if i >= saga.len() {
-        Ok(acc)
-    } else {
-        match saga[i].forward(acc) {
-            Ok(acc1) => tell_(saga, i + 1, acc1),
-            Err(Failure { state: acc1, error }) => Err(revert(saga, error, i, acc1)),
-        }
-    }
-}
+mod artifact;
+pub use crate::artifact::CompArtifact;
 
-fn revert<T>(saga: &Vec<Adventure<T>>, error: Box<Error>, i: usize, acc: T) -> Failure<T> {
-    let acc1 = saga[i].backward(acc);
-    if i == 0 {
-        Failure { error, state: acc1 }
-    } else {
-        revert(saga, error, i - 1, acc1)
-    }
-}
+mod outcome;
+pub use crate::outcome::{SagaOutcome, SagaResult};
+
+mod kind;
+pub use crate::kind::SagaErrorKind;
+
+mod saga;
+pub use crate::saga::{RollbackOrder, Saga};
+
+mod runner;
+pub use crate::runner::{SagaRunner, StepOutcome};
+
+mod state;
+pub use crate::state::SagaState;
+
+mod builder;
+pub use crate::builder::SagaBuilder;
+
+mod lazy;
+pub use crate::lazy::LazySaga;
+
+mod chunked;
+pub use crate::chunked::ChunkedSaga;
+
+mod context;
+pub use crate::context::{ContextAdventure, ContextSaga, SharedSaga};
+
+#[cfg(feature = "transactional")]
+mod transactional;
+#[cfg(feature = "transactional")]
+pub use crate::transactional::{Transactional, TxnAdventure, TxnSaga};
+
+mod hooks;
+
+mod lock;
+
+mod observer;
+pub use crate::observer::{AbortRollback, Observer};
+
+mod report;
+pub use crate::report::{RollbackReport, RollbackStep};
+
+mod progress;
+
+mod checkpoint;
+
+mod chaos;
+pub use crate::chaos::ChaosPolicy;
+
+mod trace;
+pub use crate::trace::SagaEvent;
+
+mod effects;
+pub use crate::effects::{Direction, EffectRecord};
+
+#[cfg(feature = "std")]
+mod unwind;
+
+#[cfg(feature = "std")]
+mod timing;
+#[cfg(feature = "std")]
+pub use crate::timing::StepTiming;
+
+mod pipeline;
+pub use crate::pipeline::{Pipeline, Stage, Start};
+
+#[cfg(feature = "std")]
+mod parallel;
+#[cfg(feature = "std")]
+pub use crate::parallel::ParallelStage;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "async")]
+pub use crate::asynchronous::{AbortableTell, AsyncAdventure, AsyncSaga, BoxFuture};
+
+#[cfg(test)]
+mod fixtures;
 
 #[cfg(test)]
 mod tests {
-    use Adventure;
-    use Failure;
-    use Saga;
+    use crate::fixtures::{dec, inc, inc2, StupidError};
+    use crate::Adventure;
+    use crate::Cancelled;
+    use crate::Failure;
+    use crate::Observer;
+    use crate::Saga;
+    use crate::SagaError;
+    use crate::StepLimitExceeded;
     use std::error::Error;
     use std::fmt;
 
-    #[derive(Debug)]
-    pub struct StupidError {
-        stupid: bool,
+    #[test]
+    fn good_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc2, dec), Adventure::new(inc2, dec)]);
+        match saga.tell(0) {
+            Ok(res) => assert!(res == 2),
+            Err(_) => unimplemented!(),
+        }
     }
+    #[test]
+    fn bad_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
 
-    impl Error for StupidError {
-        fn description(&self) -> &str {
-            "stupid error"
+    }
+    #[test]
+    fn reports_failed_step() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.failed_at(), 2),
+        }
+    }
+    #[test]
+    fn closure_sage() {
+        let step = 5;
+        let limit = 7;
+        let saga = Saga::new(vec![
+            Adventure::new(move |i| Ok(i + step), move |i| i - step),
+            Adventure::new(
+                move |i| if i > limit {
+                    Err(Failure::new(i, Box::new(StupidError { stupid: true })))
+                } else {
+                    Ok(i + step)
+                },
+                move |i| i - step,
+            ),
+        ]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 10),
+            Err(_) => unimplemented!(),
+        }
+        match saga.tell(3) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, -2),
+        }
+    }
+    #[test]
+    fn failed_compensation() {
+        fn refuse(i: i32) -> Result<i32, Failure<i32>> {
+            Err(Failure::new(i * 10, Box::new(StupidError { stupid: false })))
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::fallible(inc2, refuse),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                {
+                    let compensation = failure.compensation_failure().unwrap();
+                    assert_eq!(compensation.index(), 1);
+                    assert_eq!(compensation.error().to_string(), "is stupid: false");
+                }
+                assert_eq!(failure.state, 20);
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.to_error().to_string(), "is stupid: true");
+            }
+        }
+    }
+    #[test]
+    fn typed_error_sage() {
+        #[derive(Debug, PartialEq)]
+        enum Oops {
+            TooBig(i32),
+        }
+        let saga: Saga<i32, Oops> = Saga::new(vec![
+            Adventure::new(|i| Ok(i + 1), |i| i - 1),
+            Adventure::new(|i| Err(Failure::new(i, Oops::TooBig(i))), |i| i),
+        ]);
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.state, 1);
+                assert_eq!(failure.to_error(), Oops::TooBig(2));
+            }
+        }
+    }
+    #[test]
+    fn observed_sage() {
+        #[derive(Default)]
+        struct Recorder {
+            events: Vec<String>,
+        }
+        impl Observer for Recorder {
+            fn on_forward_start(&mut self, index: usize) {
+                self.events.push(format!("start {}", index));
+            }
+            fn on_forward_success(&mut self, index: usize) {
+                self.events.push(format!("success {}", index));
+            }
+            fn on_forward_failure(&mut self, index: usize, error: &Box<dyn Error>) {
+                self.events.push(format!("failure {}: {}", index, error));
+            }
+            fn on_backward(&mut self, index: usize) {
+                self.events.push(format!("backward {}", index));
+            }
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut recorder = Recorder::default();
+        assert!(saga.tell_with_observer(0, &mut recorder).is_err());
+        assert_eq!(
+            recorder.events,
+            vec![
+                "start 0",
+                "success 0",
+                "start 1",
+                "success 1",
+                "start 2",
+                "failure 2: is stupid: true",
+                "backward 2",
+                "backward 1",
+                "backward 0",
+            ]
+        );
+    }
+    #[test]
+    fn backward_sees_error() {
+        fn undo(i: i32, error: &dyn Error) -> i32 {
+            if error.to_string() == "is stupid: true" {
+                i - 10
+            } else {
+                i - 1
+            }
+        }
+        let saga = Saga::new(vec![
+            Adventure::new_with_error(inc2, |i, error| undo(i, &**error)),
+            Adventure::new(inc2, dec),
+            Adventure::new_with_error(inc2, |i, error| undo(i, &**error)),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, -18),
+        }
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn retried_sage() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+        let attempts = Arc::new(AtomicU32::new(0));
+        let flaky = |succeed_after: u32| {
+            let attempts = attempts.clone();
+            move |i: i32| {
+                if attempts.fetch_add(1, Ordering::SeqCst) + 1 > succeed_after {
+                    Ok(i + 1)
+                } else {
+                    Err(Failure::new(i, Box::new(StupidError { stupid: true }) as Box<dyn Error>))
+                }
+            }
+        };
+        let saga = Saga::new(vec![Adventure::with_retry(flaky(2), dec, 2, Duration::from_millis(1))]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 1),
+            Err(_) => unimplemented!(),
+        }
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        attempts.store(0, Ordering::SeqCst);
+        let saga = Saga::new(vec![Adventure::with_retry(flaky(3), dec, 2, Duration::from_millis(1))]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, -1),
+        }
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn counted_attempts_sage() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+        let flaky = |succeed_after: u32| {
+            let attempts = AtomicU32::new(0);
+            move |i: i32| {
+                if attempts.fetch_add(1, Ordering::SeqCst) + 1 > succeed_after {
+                    Ok(i + 1)
+                } else {
+                    Err(Failure::new(i, Box::new(StupidError { stupid: true }) as Box<dyn Error>))
+                }
+            }
+        };
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::with_retry(flaky(1), dec, 2, Duration::from_millis(1)),
+        ]);
+        match saga.tell_with_attempts(0) {
+            Ok((res, attempts)) => {
+                assert_eq!(res, 2);
+                assert_eq!(attempts, vec![(0, 1), (1, 2)]);
+            }
+            Err(_) => unimplemented!(),
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::with_retry(flaky(3), dec, 2, Duration::from_millis(1)),
+        ]);
+        match saga.tell_with_attempts(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.state, -1);
+                assert_eq!(failure.attempts(), &[(0, 1), (1, 3)]);
+            }
+        }
+        // attempts are only recorded when asked for
+        let saga = Saga::new(vec![Adventure::with_retry(flaky(3), dec, 2, Duration::from_millis(1))]);
+        assert!(saga.tell(0).unwrap_err().attempts().is_empty());
+    }
+    #[test]
+    fn inspect_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_name("first"),
+            Adventure::new(inc, dec),
+        ]);
+        assert_eq!(saga.len(), 2);
+        assert!(!saga.is_empty());
+        let names: Vec<_> = saga.iter().map(Adventure::name).collect();
+        assert_eq!(names, vec![Some("first"), None]);
+        assert!(Saga::<i32>::new(vec![]).is_empty());
+    }
+    #[test]
+    fn conditional_sage() {
+        fn double(i: i32) -> Result<i32, Failure<i32>> {
+            Ok(i * 2)
+        }
+        fn half(i: i32) -> i32 {
+            i / 2
+        }
+        let saga = Saga::new(vec![
+            Adventure::conditional(|i: &i32| *i > 0, double, half),
+            Adventure::new(inc, dec),
+            Adventure::conditional(|i: &i32| *i > 5, double, half),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 2),
+            Err(_) => unimplemented!(),
+        }
+        match saga.tell(3) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 3);
+                assert_eq!(failure.state, 3);
+            }
         }
     }
-    impl fmt::Display for StupidError {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "is stupid: {}", self.stupid)
+    #[test]
+    #[cfg(feature = "std")]
+    fn timed_out_sage() {
+        use crate::TimedOut;
+        use std::thread;
+        use std::time::Duration;
+        type SendError = Box<dyn Error + Send + Sync>;
+        fn quick(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            Ok(i + 1)
+        }
+        fn slow(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            thread::sleep(Duration::from_millis(500));
+            Ok(i + 1)
+        }
+        let saga = Saga::new(vec![
+            Adventure::with_timeout(quick, dec, Duration::from_millis(200)),
+            Adventure::with_timeout(slow, dec, Duration::from_millis(10)),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, -1);
+                assert!(failure.to_error().downcast_ref::<TimedOut>().is_some());
+            }
         }
     }
-    fn inc2(i: i32) -> Result<i32, Failure<i32>> {
-        if i >= 2 {
-            Err(Failure::new(i + 1, Box::new(StupidError { stupid: true })))
-        } else {
+    #[test]
+    #[cfg(feature = "std")]
+    fn panicked_timeout_sage() {
+        use crate::Panicked;
+        use std::time::Duration;
+        type SendError = Box<dyn Error + Send + Sync>;
+        fn quick(i: i32) -> Result<i32, Failure<i32, SendError>> {
             Ok(i + 1)
         }
+        fn explode(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            panic!("exploded at {}", i);
+        }
+        let saga = Saga::new(vec![
+            Adventure::with_timeout(quick, dec, Duration::from_secs(5)),
+            Adventure::with_timeout(explode, dec, Duration::from_secs(5)),
+        ]);
+        match saga.tell_catch_unwind(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, -1);
+                let panicked = failure.error().downcast_ref::<Panicked>().unwrap();
+                assert_eq!(panicked.message(), Some("exploded at 1"));
+            }
+        }
+        let told = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| saga.tell(0)));
+        assert!(told.is_err());
+    }
+    #[test]
+    fn owned_sage() {
+        let created = String::from("resource");
+        let released = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let release = released.clone();
+        let saga = Saga::new(vec![
+            Adventure::once(move |i: i32| Ok(i + created.len() as i32 - 7), move |i| {
+                release.lock().unwrap().push("resource");
+                i - 1
+            }),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell_owned(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+        assert_eq!(*released.lock().unwrap(), vec!["resource"]);
+    }
+    #[test]
+    #[should_panic]
+    fn borrowed_once_sage() {
+        let saga = Saga::new(vec![Adventure::once(inc, dec)]);
+        let _ = saga.tell(0);
     }
-    fn dec(i: i32) -> i32 {
-        i - 1
+    #[test]
+    fn debug_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec).with_name("inc")]);
+        let debug = format!("{:?}", saga);
+        assert!(debug.starts_with("Saga { len: 1, adventures: [Adventure { name: Some(\"inc\"), forward: Shared(0x"));
+        let failure = Failure::new(3, Box::new(StupidError { stupid: true }) as Box<dyn Error>);
+        assert_eq!(
+            format!("{:?}", failure),
+            "Failure { error: is stupid: true, state: 3, failed_at: 0, step: None, phase: None, compensations: [], aborted_at: None, rolled_back: 0, artifacts: [], attempts: [] }"
+        );
     }
     #[test]
-    fn good_sage() {
-        let saga = Saga::new(vec![Adventure::new(inc2, dec), Adventure::new(inc2, dec)]);
+    fn long_sage() {
+        fn fail_late(i: i32) -> Result<i32, Failure<i32>> {
+            if i >= 99_999 {
+                Err(Failure::new(i, Box::new(StupidError { stupid: true })))
+            } else {
+                Ok(i + 1)
+            }
+        }
+        let saga = Saga::new((0..100_000).map(|_| Adventure::new(inc, dec)).collect());
         match saga.tell(0) {
-            Ok(res) => assert!(res == 2),
+            Ok(res) => assert_eq!(res, 100_000),
             Err(_) => unimplemented!(),
         }
+        let saga = Saga::new((0..100_000).map(|_| Adventure::new(fail_late, dec)).collect());
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, -1),
+        }
     }
     #[test]
-    fn bad_sage() {
+    fn fn_pointer_sage() {
+        let saga = Saga::new(vec![Adventure::from_fns(inc2, dec)]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 1),
+            Err(_) => unimplemented!(),
+        }
+    }
+    #[test]
+    fn compensation_sage() {
+        fn charge(i: i32) -> Result<(i32, String), Failure<i32>> {
+            Ok((i + 1, format!("tx-{}", i)))
+        }
+        let refunds = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let refund = refunds.clone();
         let saga = Saga::new(vec![
+            Adventure::with_compensation(charge, move |i, tx: String| {
+                refund.lock().unwrap().push(tx);
+                i - 1
+            }),
             Adventure::new(inc2, dec),
             Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 1),
+        }
+        assert_eq!(*refunds.lock().unwrap(), vec!["tx-1"]);
+    }
+    #[test]
+    fn any_compensation_sage() {
+        use std::any::Any;
+        fn create(i: i32) -> Result<(i32, Box<dyn Any + Send>), Failure<i32>> {
+            if i == 0 {
+                Ok((i + 1, Box::new(String::from("bucket"))))
+            } else {
+                Ok((i + 1, Box::new(i)))
+            }
+        }
+        let deleted = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let delete = deleted.clone();
+        let adventure = Adventure::with_any_compensation(create, move |i, data| {
+            let data = match data.downcast::<String>() {
+                Ok(name) => *name,
+                Err(data) => format!("object-{}", data.downcast::<i32>().unwrap()),
+            };
+            delete.lock().unwrap().push(data);
+            i - 1
+        });
+        let saga = Saga::new(vec![adventure.clone(), adventure, Adventure::new(inc2, dec)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+        assert_eq!(*deleted.lock().unwrap(), vec!["object-1", "bucket"]);
+    }
+    #[test]
+    fn artifact_sage() {
+        use crate::CompArtifact;
+        let refund = |i: i32| (i - 1, vec![CompArtifact::new(format!("refund-{}", i))]);
+        let saga = Saga::new(vec![
+            Adventure::with_artifacts(inc, refund),
+            Adventure::new(inc, dec),
+            Adventure::with_artifacts(inc, refund),
             Adventure::new(inc2, dec),
         ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.state, 0);
+                let artifacts: Vec<String> = failure.artifacts().iter().map(|a| a.to_string()).collect();
+                assert_eq!(artifacts, vec!["step 2: refund-3", "step 0: refund-1"]);
+            }
+        }
+        assert!(saga.tell(-2).is_ok());
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn threaded_artifact_sage() {
+        use crate::CompArtifact;
+        use std::sync::{Arc, Barrier};
+        use std::thread;
+        // both tells refund at the same time
+        let barrier = Arc::new(Barrier::new(2));
+        let refund = move |i: i32| {
+            barrier.wait();
+            (i - 1, vec![CompArtifact::new(format!("refund-{}", i))])
+        };
+        let saga = Saga::new(vec![
+            Adventure::with_artifacts(inc, refund.clone()),
+            Adventure::with_artifacts(inc, refund),
+            Adventure::new(
+                |i| Err(Failure::new(i, Box::new(StupidError { stupid: true }) as Box<dyn Error>)),
+                dec,
+            ),
+        ]);
+        let artifacts = |acc: i32| {
+            let failure = saga.tell(acc).unwrap_err();
+            failure.artifacts().iter().map(|a| a.to_string()).collect::<Vec<_>>()
+        };
+        let (low, high) = thread::scope(|scope| {
+            let low = scope.spawn(|| artifacts(0));
+            let high = scope.spawn(|| artifacts(10));
+            (low.join().unwrap(), high.join().unwrap())
+        });
+        assert_eq!(low, vec!["step 1: refund-1", "step 0: refund-0"]);
+        assert_eq!(high, vec!["step 1: refund-11", "step 0: refund-10"]);
+    }
+    #[test]
+    fn failed_compensation_step() {
+        fn charge(i: i32) -> Result<(i32, String), Failure<i32>> {
+            Err(Failure::new(i, Box::new(StupidError { stupid: true })))
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::with_compensation(charge, |_, _: String| unimplemented!()),
+        ]);
         match saga.tell(0) {
             Ok(_) => unimplemented!(),
             Err(Failure { state: res, .. }) => assert_eq!(res, 0),
         }
-
+    }
+    #[test]
+    fn pure_sage() {
+        fn double(i: i32) -> i32 {
+            i * 2
+        }
+        let saga = Saga::new(vec![
+            Adventure::infallible(double, |i| i / 2),
+            Adventure::pure(inc),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 2),
+            Err(_) => unimplemented!(),
+        }
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 1),
+        }
+    }
+    #[test]
+    fn batch_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        match saga.tell_each(vec![0, 0, 0]) {
+            Ok(res) => assert_eq!(res, vec![2, 2, 2]),
+            Err(_) => unimplemented!(),
+        }
+        match saga.tell_each(vec![0, 0, 1, 5]) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, vec![0, 0, 1, 5]);
+            }
+        }
+    }
+    #[test]
+    fn saga_per_thread() {
+        fn send_sync<S: Send + Sync>(_: &S) {}
+        fn saga() -> Saga<i32, Box<dyn Error + Send + Sync>> {
+            Saga::new(vec![Adventure::new(
+                |i| Err(Failure::new(i + 1, "failed".into())),
+                |i| i - 1,
+            )])
+        }
+        let told: Vec<_> = (0..4)
+            .map(|i| std::thread::spawn(move || saga().tell(i)))
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        for (i, res) in told.iter().enumerate() {
+            let failure = res.as_ref().unwrap_err();
+            send_sync(failure);
+            assert_eq!(failure.state, i as i32);
+        }
+    }
+    #[test]
+    fn completed_sage() {
+        use crate::Control;
+        fn done(i: i32) -> Result<Control<i32>, Failure<i32>> {
+            if i >= 1 {
+                Ok(Control::Complete(i * 10))
+            } else {
+                Ok(Control::Continue(i))
+            }
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::with_control(done, |i| i / 10),
+            Adventure::new(inc2, dec).with_commit(|i| i + 100),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(saga.tell(0).ok(), Some(10));
+        assert_eq!(saga.tell(-1).ok(), Some(102));
+        let saga = Saga::new(vec![
+            Adventure::with_control(done, |i| i / 10),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(saga.tell(0).ok(), Some(1));
+        assert_eq!(saga.tell(2).ok(), Some(20));
+    }
+    #[test]
+    fn persisted_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec).with_name("second"),
+            Adventure::new(inc, dec),
+        ]);
+        let mut log = Vec::new();
+        let res = saga.tell_persisted(0, |i, acc| {
+            log.push((i, *acc));
+            Ok(())
+        });
+        assert_eq!(res.ok(), Some(3));
+        assert_eq!(log, vec![(0, 1), (1, 2), (2, 3)]);
+        let res = saga.tell_persisted(0, |i, _| {
+            if i == 1 {
+                Err("audit log is full".into())
+            } else {
+                Ok(())
+            }
+        });
+        match res {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.step_name(), Some("second"));
+                assert_eq!(failure.state, 0);
+                assert_eq!(failure.error().to_string(), "audit log is full");
+            }
+        }
+    }
+    #[test]
+    fn strict_sage() {
+        use crate::EmptySaga;
+        assert_eq!(Saga::<i32>::empty().tell(1).ok(), Some(1));
+        match Saga::<i32>::empty().strict().tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.state, 1);
+                assert!(failure.to_error().downcast_ref::<EmptySaga>().is_some());
+            }
+        }
+        let saga = Saga::new(vec![Adventure::new(inc, dec)]).strict();
+        assert_eq!(saga.tell(1).ok(), Some(2));
+    }
+    #[test]
+    fn phased_sage() {
+        #[derive(Default)]
+        struct Phases {
+            entered: Vec<(usize, String)>,
+        }
+        impl Observer for Phases {
+            fn on_phase(&mut self, index: usize, phase: &str) {
+                self.entered.push((index, phase.to_string()));
+            }
+        }
+        let saga = Saga::new(vec![Adventure::new(inc, dec)])
+            .phase("reserve", vec![Adventure::new(inc, dec), Adventure::new(inc, dec)])
+            .phase("charge", vec![Adventure::new(inc2, dec)]);
+        assert_eq!(saga.phases(), vec![("reserve", 1..3), ("charge", 3..4)]);
+        let mut phases = Phases::default();
+        match saga.tell_with_observer(0, &mut phases) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.state, 0);
+                assert_eq!(failure.phase(), Some("charge"));
+            }
+        }
+        assert_eq!(
+            phases.entered,
+            vec![
+                (1, "reserve".to_string()),
+                (3, "charge".to_string()),
+                (2, "reserve".to_string()),
+            ]
+        );
+        let saga: Saga<i32> = Saga::empty()
+            .phase(
+                "reserve",
+                vec![Adventure::fallible(inc, |i| Err(Failure::new(i, "stuck".into())))],
+            )
+            .phase("charge", vec![Adventure::new(inc2, dec)]);
+        let failure = saga.tell(1).unwrap_err();
+        assert_eq!(failure.compensation_failure().unwrap().phase(), Some("reserve"));
+    }
+    #[test]
+    fn folded_sage() {
+        type Step = (fn(i32) -> Result<i32, Failure<i32>>, fn(i32) -> i32);
+        let steps: Vec<Step> = vec![(inc, dec), (inc, dec)];
+        assert_eq!(Saga::fold(0, steps).ok(), Some(2));
+        let steps: Vec<Step> = vec![(inc2, dec), (inc2, dec), (inc2, dec)];
+        match Saga::fold(0, steps) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn paired_sage() {
+        let saga = Saga::from_pairs(&[(inc, dec), (inc, dec)]);
+        assert_eq!(saga.len(), 2);
+        assert_eq!(saga.tell(0).ok(), Some(2));
+        let saga = Saga::from_pairs(&[(inc2, dec), (inc2, dec), (inc2, dec)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn inspected_sage() {
+        use std::cell::RefCell;
+        let seen = RefCell::new(Vec::new());
+        let inspect = |index: usize, acc: &i32| seen.borrow_mut().push((index, *acc));
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell_inspect(0, Some(&inspect)) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+        assert_eq!(*seen.borrow(), vec![(0, 1), (1, 2)]);
+        assert_eq!(saga.tell_inspect(0, None).map_err(|f| f.state).unwrap_err(), 0);
+    }
+    #[test]
+    fn reverted_sage() {
+        let reason = || -> Box<dyn Error> { Box::new(StupidError { stupid: false }) };
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::with_input_snapshot(inc, |_, input| input - 10),
+            Adventure::new(inc, dec),
+        ]);
+        let res = saga.tell(0).ok().unwrap();
+        assert_eq!(saga.revert_all(res, reason()).ok(), Some(1));
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::irreversible(inc),
+            Adventure::new(inc, dec),
+        ]);
+        match saga.revert_all(3, reason()) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.compensation_failures().len(), 1);
+                assert_eq!(failure.state, 1);
+            }
+        }
+    }
+    #[test]
+    fn tagged_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).tag("system", "external_api"),
+            Adventure::new(inc, dec).tag("system", "database"),
+            Adventure::new(inc, dec)
+                .tag("system", "database")
+                .tag("system", "external_api")
+                .tag("team", "billing"),
+        ]);
+        assert_eq!(saga.steps_with_tag("system", "external_api"), vec![0, 2]);
+        assert_eq!(saga.steps_with_tag("team", "billing"), vec![2]);
+        assert!(saga.steps_with_tag("team", "database").is_empty());
+        assert_eq!(saga.adventures[2].tags().len(), 2);
+        let adventure = Adventure::new(inc, dec);
+        assert!(adventure.clone().tag("system", "database") != adventure);
+    }
+    #[test]
+    fn registered_compensations_sage() {
+        use std::sync::{Arc, Mutex};
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let created = log.clone();
+        let create = Adventure::with_compensations(move |acc: i32, compensations| {
+            for resource in 0..3 {
+                let log = created.clone();
+                compensations.register_compensation(move |acc| {
+                    log.lock().unwrap().push(resource);
+                    acc - 1
+                });
+            }
+            Ok(acc + 3)
+        });
+        let saga = Saga::new(vec![create, Adventure::new(inc2, dec)]);
+        assert_eq!(saga.tell(-2).ok(), Some(2));
+        assert!(log.lock().unwrap().is_empty());
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+        assert_eq!(*log.lock().unwrap(), vec![2, 1, 0]);
+        let partial = Adventure::<i32>::with_compensations(|acc, compensations| {
+            compensations.register_compensation(|acc| acc - 1);
+            Err(Failure::new(acc + 1, Box::new(StupidError { stupid: true })))
+        });
+        match Saga::new(vec![Adventure::new(inc, dec), partial]).tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn equal_sage() {
+        let built = Saga::new(vec![
+            Adventure::from_fns(inc, dec).with_name("first"),
+            Adventure::from_fns(inc2, dec),
+        ]);
+        let expected = Saga::new(vec![
+            Adventure::from_fns(inc, dec).with_name("first"),
+            Adventure::from_fns(inc2, dec),
+        ]);
+        assert_eq!(built, expected);
+        let renamed = Saga::new(vec![
+            Adventure::from_fns(inc, dec).with_name("second"),
+            Adventure::from_fns(inc2, dec),
+        ]);
+        assert_ne!(built, renamed);
+        assert_ne!(built, Saga::new(vec![Adventure::from_fns(inc, dec).with_name("first")]));
+        let closure: Adventure<i32> = Adventure::new(|i: i32| Ok(i + 1), |i| i - 1);
+        assert_eq!(closure.clone(), closure);
+        assert_ne!(Adventure::new(inc, dec), Adventure::new(inc, dec));
+        let defaulted = built.clone().with_default_backward(|i| i - 1);
+        assert_eq!(defaulted.clone(), defaulted);
+        assert_ne!(defaulted, built);
+        assert_ne!(defaulted, built.clone().with_default_backward(|i| i - 1));
+        let reported = built.clone().on_rollback(|_, _, _| ());
+        assert_eq!(reported.clone(), reported);
+        assert_ne!(reported, built);
+    }
+    #[test]
+    fn macro_sage() {
+        let saga: Saga<i32> = crate::saga![inc => dec, "twice": inc2 => dec, inc2 => dec,];
+        assert_eq!(saga.len(), 3);
+        assert_eq!(saga.iter().nth(1).and_then(|adventure| adventure.name()), Some("twice"));
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+        let saga: Saga<i32> = crate::saga![];
+        assert!(saga.is_empty());
+    }
+    #[test]
+    fn rollback_callback_sage() {
+        use std::sync::{Arc, Mutex};
+        let rollbacks = Arc::new(Mutex::new(Vec::new()));
+        let seen = rollbacks.clone();
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ])
+        .on_rollback(move |failed_at, error, reverted| {
+            seen.lock().unwrap().push((failed_at, error.to_string(), reverted));
+        });
+        assert!(saga.tell(-2).is_ok());
+        assert!(saga.tell(0).is_err());
+        assert!(saga.clone().tell_best_effort(0).is_err());
+        assert_eq!(
+            *rollbacks.lock().unwrap(),
+            vec![(2, "is stupid: true".to_string(), 3), (2, "is stupid: true".to_string(), 3)]
+        );
+    }
+    #[test]
+    fn warned_sage() {
+        use crate::Warning;
+        fn warn(i: i32) -> Result<(i32, Vec<Warning>), Failure<i32>> {
+            Ok((i + 1, vec![Warning::new(format!("deprecated {}", i))]))
+        }
+        let saga = Saga::new(vec![
+            Adventure::with_warnings(warn, dec),
+            Adventure::new(inc, dec),
+            Adventure::with_warnings(warn, dec),
+        ]);
+        let (res, warnings) = saga.tell_with_warnings(0).ok().unwrap();
+        assert_eq!(res, 3);
+        let warnings: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings, vec!["step 0: deprecated 0", "step 2: deprecated 2"]);
+        let saga = Saga::new(vec![
+            Adventure::with_warnings(warn, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell_with_warnings(1) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 1),
+        }
+        assert_eq!(saga.tell(0).ok(), Some(2));
+    }
+    #[test]
+    fn irreversible_step_sage() {
+        use crate::IrreversibleStepRolledBack;
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::irreversible(inc),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(saga.tell(-1).ok(), Some(2));
+        let failure = saga.tell(0).unwrap_err();
+        assert_eq!(failure.state, 1);
+        assert_eq!(failure.rolled_back(), 2);
+        let compensation = failure.compensation_failure().unwrap();
+        assert_eq!(compensation.index(), 1);
+        assert!(compensation.error().downcast_ref::<IrreversibleStepRolledBack>().is_some());
+    }
+    #[test]
+    fn snapshotted_sage() {
+        fn times10(i: i32) -> Result<i32, Failure<i32, Box<dyn Error>>> {
+            Ok(i * 10)
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(times10, |i| i + 100),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(saga.tell(0).unwrap_err().state, 109);
+        // each backward step sees the state its own forward step left
+        let failure = saga.tell_snapshotted(0).unwrap_err();
+        assert_eq!(failure.state, 0);
+        assert_eq!(failure.rolled_back(), 3);
+        assert_eq!(saga.tell_snapshotted(-1).ok(), Some(1));
+    }
+    #[test]
+    fn error_context_sage() {
+        #[derive(Debug)]
+        struct CleanedUp {
+            index: usize,
+            source: Box<dyn Error>,
+        }
+        impl Error for CleanedUp {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&*self.source)
+            }
+        }
+        impl fmt::Display for CleanedUp {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "cleaned up step {}", self.index)
+            }
+        }
+        fn cleaned_up(source: Box<dyn Error>, index: usize) -> Box<dyn Error> {
+            Box::new(CleanedUp { index, source })
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_error_context(cleaned_up),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec).with_error_context(cleaned_up),
+        ]);
+        let failure = saga.tell(0).unwrap_err();
+        assert_eq!(failure.state, 0);
+        let mut chain = Vec::new();
+        let mut error: Option<&dyn Error> = Some(&**failure.error());
+        while let Some(e) = error {
+            chain.push(e.to_string());
+            error = e.source();
+        }
+        assert_eq!(chain, vec!["cleaned up step 0", "cleaned up step 2", "is stupid: true"]);
+    }
+    #[test]
+    fn invertible_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, |i| i - 2),
+            Adventure::new(inc2, dec),
+            Adventure::fallible(inc, |i| Err(Failure::new(i, Box::new(StupidError { stupid: true }) as Box<dyn Error>))),
+        ]);
+        assert_eq!(saga.validate_invertible(&[0, 1, 5]), Err(vec![1, 3]));
+        // inc2 fails for 5 so it is only checked with the other samples
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, |i| i)]);
+        assert_eq!(saga.validate_invertible(&[5]), Ok(()));
+        assert_eq!(saga.validate_invertible(&[0, 5]), Err(vec![1]));
+    }
+    #[test]
+    fn default_backward_sage() {
+        use crate::IrreversibleStepRolledBack;
+        let adventures = vec![
+            Adventure::forward_only(inc),
+            Adventure::new(inc, |i| i - 10),
+            Adventure::new(inc2, dec),
+        ];
+        // the default only stands in for the adventure without a backward step
+        let saga = Saga::new(adventures.clone()).with_default_backward(dec);
+        let failure = saga.tell(0).unwrap_err();
+        assert_eq!(failure.state, -9);
+        assert_eq!(failure.rolled_back(), 3);
+        let saga = Saga::new(adventures);
+        let failure = saga.tell(0).unwrap_err();
+        assert_eq!(failure.state, -8);
+        assert_eq!(failure.rolled_back(), 2);
+        let compensation = failure.compensation_failure().unwrap();
+        assert_eq!(compensation.index(), 0);
+        assert!(compensation.error().downcast_ref::<IrreversibleStepRolledBack>().is_some());
+        // a nested saga uses its own default
+        let inner = Saga::new(vec![Adventure::forward_only(inc)]).with_default_backward(dec);
+        let saga = Saga::new(vec![Adventure::from_saga(inner), Adventure::new(inc2, dec)]);
+        assert_eq!(saga.tell(1).unwrap_err().state, 1);
+    }
+    #[test]
+    fn tell_until_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec)]);
+        assert_eq!(saga.tell_until(0, |i| *i >= 3, 10).ok(), Some(3));
+        assert_eq!(saga.tell_until(0, |i| *i >= 3, 2).ok(), Some(2));
+        let saga = Saga::new(vec![Adventure::new(inc2, dec)]);
+        match saga.tell_until(0, |i| *i >= 5, 10) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 2),
+        }
+    }
+    #[test]
+    fn dry_run_sage() {
+        fn small(i: &i32) -> Result<(), Box<dyn Error>> {
+            if *i > 3 {
+                Err(Box::new(StupidError { stupid: true }))
+            } else {
+                Ok(())
+            }
+        }
+        let told = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let tell = told.clone();
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(
+                move |i| {
+                    tell.store(true, std::sync::atomic::Ordering::SeqCst);
+                    Ok(i)
+                },
+                dec,
+            )
+            .with_validator(small),
+        ]);
+        assert_eq!(saga.dry_run(3).ok(), Some(3));
+        match saga.dry_run(4) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, 4);
+            }
+        }
+        assert!(!told.load(std::sync::atomic::Ordering::SeqCst));
+    }
+    #[test]
+    fn nested_sage() {
+        let inner = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc, dec)]);
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::from_saga(inner),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(-2) {
+            Ok(res) => assert_eq!(res, 2),
+            Err(_) => unimplemented!(),
+        }
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn failed_nested_sage() {
+        fn refuse(i: i32) -> Result<i32, Failure<i32>> {
+            Err(Failure::new(i, Box::new(StupidError { stupid: false })))
+        }
+        let inner = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::from_saga(inner)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, 0);
+            }
+        }
+        let inner = Saga::new(vec![Adventure::fallible(inc, refuse), Adventure::new(inc2, dec)]);
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::from_saga(inner)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.compensation_failure().map(|c| c.index()), Some(1));
+                assert_eq!(failure.state, 2);
+            }
+        }
+    }
+    #[test]
+    fn question_mark_sage() {
+        use crate::ResultExt;
+        fn add(i: i32, s: &str) -> Result<i32, Failure<i32>> {
+            let n: i32 = s.parse().or_fail(|| i)?;
+            Ok(i + n)
+        }
+        let saga: Saga<i32> = Saga::new(vec![
+            Adventure::new(|i| add(i, "2"), dec),
+            Adventure::new(|i| add(i, "x"), dec),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert!(failure.to_error().downcast_ref::<std::num::ParseIntError>().is_some());
+            }
+        }
+        let failure: Failure<i32> = Failure::from_error(1, StupidError { stupid: true });
+        assert_eq!(failure.to_error().to_string(), "is stupid: true");
+    }
+    #[test]
+    fn question_mark_state_sage() {
+        use crate::ResultExt;
+        fn add(i: i32, s: &str) -> Result<i32, Failure<i32>> {
+            let n: i32 = s.parse().or_fail_state(i)?;
+            Ok(i + n)
+        }
+        let saga: Saga<i32> = Saga::new(vec![
+            Adventure::new(|i| add(i, "2"), dec),
+            Adventure::new(|i| add(i, "x"), dec),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, 0);
+                assert!(failure.to_error().downcast_ref::<std::num::ParseIntError>().is_some());
+            }
+        }
+    }
+    #[test]
+    fn failure_parts() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let failure = saga.tell(1).err().unwrap();
+        assert_eq!(failure.error().to_string(), "is stupid: true");
+        assert_eq!(*failure.state(), 1);
+        let (error, acc) = failure.into_parts();
+        assert_eq!(error.to_string(), "is stupid: true");
+        assert_eq!(acc, 1);
+        assert_eq!(saga.tell(1).err().map(Failure::into_acc), Some(1));
+    }
+    #[test]
+    fn cloned_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec).with_name("inc"), Adventure::new(inc2, dec)]);
+        let mut clone = saga.clone();
+        clone.push(Adventure::new(inc2, dec));
+        assert_eq!(saga.len(), 2);
+        assert_eq!(clone.iter().next().and_then(Adventure::name), Some("inc"));
+        assert_eq!(saga.tell(0).ok(), Some(2));
+        match clone.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn send_sync_sage() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<Saga<i32>>();
+        assert_send_sync::<Adventure<i32>>();
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn sent_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let clone = saga.clone();
+        let told = std::thread::spawn(move || clone.tell(-2).ok()).join().unwrap();
+        assert_eq!(told, Some(0));
+        assert!(saga.tell(1).is_err());
+    }
+    #[test]
+    #[should_panic]
+    fn cloned_once_sage() {
+        let _ = Saga::new(vec![Adventure::once(inc, dec)]).clone();
+    }
+    #[test]
+    fn inverse_sage() {
+        fn double(i: i32) -> Result<i32, Failure<i32>> {
+            Ok(i * 2)
+        }
+        fn half(i: i32) -> Result<i32, Failure<i32>> {
+            if i % 2 == 1 {
+                Err(Failure::new(i, Box::new(StupidError { stupid: true })))
+            } else {
+                Ok(i / 2)
+            }
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_name("inc"),
+            Adventure::fallible(double, half),
+        ]);
+        let inverse = saga.inverse(|| Box::new(StupidError { stupid: false }) as Box<dyn Error>);
+        assert_eq!(inverse.iter().last().and_then(Adventure::name), Some("inc"));
+        assert_eq!(inverse.tell(6).ok(), Some(2));
+        match inverse.tell(5) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 10),
+        }
+    }
+    #[test]
+    fn aborted_sage() {
+        use crate::AbortRollback;
+        struct Guard {
+            seen: Vec<usize>,
+        }
+        impl Observer for Guard {
+            fn on_backward(&mut self, index: usize) {
+                self.seen.push(index);
+            }
+            fn before_backward(&mut self, index: usize) -> Result<(), AbortRollback> {
+                if index == 1 {
+                    Err(AbortRollback)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut guard = Guard { seen: Vec::new() };
+        match saga.tell_with_observer(0, &mut guard) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.aborted_at(), Some(1));
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, 2);
+            }
+        }
+        assert!(!guard.seen.contains(&1));
+        assert_eq!(guard.seen, vec![2]);
+    }
+    #[test]
+    fn mut_sage() {
+        let mut created = Vec::new();
+        let mut deleted = 0;
+        let saga = Saga::new(vec![
+            Adventure::new_mut(
+                move |i| {
+                    created.push(i);
+                    Ok(i + created.len() as i32)
+                },
+                move |i| {
+                    deleted += 1;
+                    i - deleted
+                },
+            ),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(saga.tell(0).ok(), Some(2));
+        assert_eq!(saga.tell(0).ok(), None);
+    }
+    #[test]
+    fn prioritized_sage() {
+        let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let step = |n: usize| {
+            let log = log.clone();
+            Adventure::new(inc, move |i| {
+                log.lock().unwrap().push(n);
+                i - 1
+            })
+        };
+        let saga = Saga::new(vec![
+            step(0).with_rollback_priority(1),
+            step(1),
+            step(2).with_rollback_priority(1),
+            step(3),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+        assert_eq!(*log.lock().unwrap(), vec![2, 0, 3, 1]);
+    }
+    #[test]
+    fn ordered_rollback_sage() {
+        use crate::RollbackOrder;
+        let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let step = |n: usize| {
+            let log = log.clone();
+            Adventure::new(inc2, move |i| {
+                log.lock().unwrap().push(n);
+                i - 1
+            })
+        };
+        let saga = Saga::new(vec![step(0), step(1), step(2)]);
+        for (order, expected) in [
+            (RollbackOrder::Reverse, vec![2, 1, 0]),
+            (RollbackOrder::Forward, vec![0, 1, 2]),
+        ] {
+            log.lock().unwrap().clear();
+            match saga.clone().with_rollback_order(order).tell(0) {
+                Ok(_) => unimplemented!(),
+                Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+            }
+            assert_eq!(*log.lock().unwrap(), expected);
+        }
+        assert!(saga.clone() == saga.clone().with_rollback_order(RollbackOrder::Reverse));
+        assert!(saga.clone() != saga.with_rollback_order(RollbackOrder::Forward));
+    }
+    #[test]
+    fn best_effort_sage() {
+        fn refuse(i: i32) -> Result<i32, Failure<i32>> {
+            Err(Failure::new(i, Box::new(StupidError { stupid: false })))
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::fallible(inc, refuse),
+            Adventure::new(inc, dec),
+            Adventure::fallible(inc, refuse),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell_best_effort(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                let failed: Vec<_> = failure.compensation_failures().iter().map(|c| c.index()).collect();
+                assert_eq!(failed, vec![3, 1]);
+                assert_eq!(failure.state, 2);
+            }
+        }
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.compensation_failures().len(), 1);
+                assert_eq!(failure.state, 4);
+            }
+        }
+    }
+    #[test]
+    fn reversible_sage() {
+        let saga = Saga::new(vec![
+            Adventure::reversible(|i| i + 5, |i| i - 5, 0),
+            Adventure::reversible(|i| i * 2, |i| i / 2, 1),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(2) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 2),
+        }
+    }
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the inverse does not revert the step")]
+    fn irreversible_sage() {
+        let _: Adventure<i32> = Adventure::reversible(|i| i + 5, |i| i - 4, 0);
+    }
+    #[test]
+    fn idempotent_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_idempotency_check(|i| *i >= 1),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 0),
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec).with_idempotency_check(|i| *i >= 1),
+            Adventure::new(inc, dec),
+        ]);
+        match saga.tell(5) {
+            Ok(res) => assert_eq!(res, 6),
+            Err(_) => unimplemented!(),
+        }
+    }
+    #[test]
+    fn two_phase_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_commit(|i| i * 10),
+            Adventure::new(inc, dec).with_commit(|i| i + 5),
+        ]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 25),
+            Err(_) => unimplemented!(),
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_commit(|i| i * 10),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(2) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 2),
+        }
+    }
+    #[test]
+    fn chained_sage() {
+        let first = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc, dec)]);
+        let second = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let saga = first.then(second);
+        assert_eq!(saga.len(), 4);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 3);
+                assert_eq!(failure.state, 0);
+            }
+        }
+    }
+    #[test]
+    fn empty_sage() {
+        let saga: Saga<i32> = Saga::empty();
+        assert!(saga.is_empty());
+        assert_eq!(saga.tell(7).ok(), Some(7));
+        let saga = Saga::empty().then(Saga::new(vec![Adventure::new(inc, dec)])).then(Saga::empty());
+        assert_eq!(saga.len(), 1);
+        assert_eq!(saga.tell(7).ok(), Some(8));
+    }
+    #[test]
+    fn named_error_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_name("reserve"),
+            Adventure::new(inc2, dec).with_name("charge_card"),
+        ]);
+        let failure = saga.tell(2).unwrap_err();
+        assert_eq!(failure.step_name(), Some("charge_card"));
+        let error = SagaError::from(failure);
+        assert_eq!(error.to_string(), "saga failed at step 'charge_card': is stupid: true");
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let error = SagaError::from(saga.tell(2).unwrap_err());
+        assert_eq!(error.to_string(), "saga failed at step 1: is stupid: true");
+    }
+    #[test]
+    fn failure_error_sage() {
+        fn told() -> Result<i32, Box<dyn Error>> {
+            let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+            Ok(saga.tell(2)?)
+        }
+        let error = told().unwrap_err();
+        assert_eq!(error.to_string(), "saga failed at step 1");
+        assert_eq!(error.source().unwrap().to_string(), "is stupid: true");
+    }
+    #[test]
+    fn in_place_sage() {
+        fn push(v: &mut Vec<i32>) -> Result<(), Box<dyn Error>> {
+            v.push(v.len() as i32);
+            Ok(())
+        }
+        fn pop(v: &mut Vec<i32>) {
+            v.pop();
+        }
+        fn full(v: &mut Vec<i32>) -> Result<(), Box<dyn Error>> {
+            if v.len() >= 2 {
+                Err(Box::new(StupidError { stupid: true }))
+            } else {
+                push(v)
+            }
+        }
+        let saga = Saga::new(vec![Adventure::in_place(push, pop), Adventure::in_place(push, pop)]);
+        let mut acc = Vec::new();
+        assert!(saga.tell_in_place(&mut acc).is_ok());
+        assert_eq!(acc, vec![0, 1]);
+        let saga = Saga::new(vec![
+            Adventure::in_place(push, pop),
+            Adventure::in_place(push, pop),
+            Adventure::in_place(full, |_| ()),
+        ]);
+        let mut acc = Vec::new();
+        let failure = saga.tell_in_place(&mut acc).unwrap_err();
+        assert_eq!(failure.failed_at(), 2);
+        assert!(acc.is_empty());
+    }
+    #[test]
+    fn in_place_borrowed_sage() {
+        /// Neither `Default` nor `Clone`, so it can only be borrowed
+        struct Ledger(Vec<i32>);
+        fn book(ledger: &mut Ledger) -> Result<(), Box<dyn Error>> {
+            if ledger.0.len() >= 2 {
+                return Err(Box::new(StupidError { stupid: true }));
+            }
+            ledger.0.push(1);
+            Ok(())
+        }
+        fn cancel(ledger: &mut Ledger) {
+            ledger.0.push(-1);
+        }
+        let saga = Saga::new(vec![
+            Adventure::in_place(book, cancel),
+            Adventure::in_place(book, cancel),
+        ]);
+        let mut ledger = Ledger(Vec::new());
+        assert!(saga.tell_in_place(&mut ledger).is_ok());
+        assert_eq!(ledger.0, vec![1, 1]);
+        let failure = saga.tell_in_place(&mut ledger).unwrap_err();
+        assert_eq!(failure.failed_at(), 0);
+        assert_eq!(ledger.0, vec![1, 1, -1]);
+    }
+    #[test]
+    #[should_panic]
+    fn in_place_by_value_sage() {
+        let saga = Saga::new(vec![
+            Adventure::in_place(|_: &mut i32| Ok(()), |_| ()),
+            Adventure::new(inc, dec),
+        ]);
+        let _ = saga.tell_in_place(&mut 0);
+    }
+    #[test]
+    fn cancelled_sage() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(
+                move |i| {
+                    flag.store(true, Ordering::Release);
+                    inc(i)
+                },
+                dec,
+            ),
+            Adventure::new(inc, dec),
+        ]);
+        match saga.tell_cancellable(0, &cancel) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, 0);
+                assert!(failure.error().downcast_ref::<Cancelled>().is_some());
+            }
+        }
+        cancel.store(false, Ordering::Release);
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc, dec)]);
+        assert_eq!(saga.tell_cancellable(0, &cancel).ok(), Some(2));
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn deadline_sage() {
+        use crate::DeadlineExceeded;
+        use std::thread;
+        use std::time::{Duration, Instant};
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(
+                |i| {
+                    thread::sleep(Duration::from_millis(20));
+                    inc(i)
+                },
+                dec,
+            ),
+            Adventure::new(inc, dec),
+        ]);
+        match saga.tell_deadline(0, Instant::now() + Duration::from_millis(10)) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, 0);
+                assert!(failure.error().downcast_ref::<DeadlineExceeded>().is_some());
+            }
+        }
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(saga.tell_deadline(0, deadline).ok(), Some(3));
+    }
+    #[test]
+    fn counted_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc, dec),
+        ]);
+        assert_eq!(saga.tell(0).unwrap_err().rolled_back(), 3);
+        fn refuse(i: i32) -> Result<i32, Failure<i32>> {
+            Err(Failure::new(i, Box::new(StupidError { stupid: false })))
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::fallible(inc, refuse),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(saga.tell(0).unwrap_err().rolled_back(), 1);
+    }
+    #[test]
+    fn fallback_sage() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::with_fallback(inc2, |i, _| Ok(i - 10), dec)]);
+        match saga.tell(2) {
+            Ok(res) => assert_eq!(res, -6),
+            Err(_) => unimplemented!(),
+        }
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::with_fallback(inc2, |i, _| inc2(i), dec)]);
+        match saga.tell(2) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 3),
+        }
+    }
+    #[test]
+    fn decomposed_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_name("a"),
+            Adventure::new(inc2, dec).with_name("b"),
+        ]);
+        let mut adventures = saga.into_adventures();
+        adventures.reverse();
+        let mut saga = Saga::new(adventures);
+        assert_eq!(saga.iter().next().and_then(|a| a.name()), Some("b"));
+        saga.adventures_mut().retain(|a| a.name() == Some("a"));
+        assert_eq!(saga.len(), 1);
+    }
+    #[test]
+    fn limited_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+        ]);
+        assert_eq!(saga.clone().with_max_steps(3).tell(0).ok(), Some(3));
+        match saga.with_max_steps(2).tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, 0);
+                let error = failure.error().downcast_ref::<StepLimitExceeded>().unwrap();
+                assert_eq!(error.limit(), 2);
+            }
+        }
+    }
+    #[test]
+    fn snapshot_sage() {
+        fn square(i: i32) -> Result<i32, Failure<i32>> {
+            Ok(i * i)
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::with_input_snapshot(square, |_, input| input),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(2) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 2),
+        }
+    }
+    #[test]
+    fn diagram_sage() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_name("reserve"),
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec).with_name("ship"),
+        ]);
+        assert_eq!(saga.render_diagram(), "reserve -> 1 -> ship\nreserve <- 1 <- ship");
+        assert_eq!(Saga::<i32>::empty().render_diagram(), "");
+    }
+    #[test]
+    fn pushed_sage() {
+        let mut saga = Saga::new(vec![Adventure::new(inc2, dec)]);
+        saga.push(Adventure::new(inc2, dec));
+        assert_eq!(saga.len(), 2);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 2),
+            Err(_) => unimplemented!(),
+        }
+        saga.extend(vec![Adventure::new(inc2, dec)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 0),
+        }
+    }
+    #[test]
+    fn expanding_sage() {
+        fn expand(i: i32) -> Result<(i32, Vec<Adventure<i32>>), Failure<i32>> {
+            let more = (0..i).map(|_| Adventure::new(inc, dec)).collect();
+            Ok((i, more))
+        }
+        let saga = Saga::new(vec![
+            Adventure::expanding(expand, |i| i),
+            Adventure::new(inc, dec),
+        ]);
+        match saga.tell(2) {
+            Ok(res) => assert_eq!(res, 5),
+            Err(_) => unimplemented!(),
+        }
+        let saga = Saga::new(vec![
+            Adventure::expanding(expand, |i| i),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 1),
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::expanding(|i| Ok((i, vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)])), |i| i),
+        ]);
+        match saga.tell(1) {
+            Ok(_) => unimplemented!(),
+            Err(Failure { state: res, .. }) => assert_eq!(res, 1),
+        }
     }
 
 }
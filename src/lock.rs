@@ -0,0 +1,69 @@
+//! A lock for state that clones of an adventure share between threads.
+//!
+//! With `std` this is a `Mutex`. Without it `core` has no lock that
+//! blocks, so a spin lock is used instead.
+
+#[cfg(not(feature = "std"))]
+use core::cell::UnsafeCell;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Guards a value so only one thread reaches it at a time.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub(crate) struct Lock<T>(std::sync::Mutex<T>);
+
+#[cfg(feature = "std")]
+impl<T> Lock<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Lock(std::sync::Mutex::new(value))
+    }
+    /// Runs `f` on the locked value, a panic of an earlier holder does
+    /// not poison it
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut value = self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        f(&mut value)
+    }
+}
+
+/// Guards a value so only one thread reaches it at a time.
+#[cfg(not(feature = "std"))]
+#[derive(Default)]
+pub(crate) struct Lock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value is only reached by the thread holding the lock
+#[cfg(not(feature = "std"))]
+unsafe impl<T: Send> Sync for Lock<T> {}
+
+#[cfg(not(feature = "std"))]
+impl<T> Lock<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Lock {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+    /// Runs `f` on the locked value, spinning until it is free
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        /// Releases the lock, also when `f` panics
+        struct Unlock<'a>(&'a AtomicBool);
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let _unlock = Unlock(&self.locked);
+        // SAFETY: the lock is held until `_unlock` is dropped
+        f(unsafe { &mut *self.value.get() })
+    }
+}
@@ -0,0 +1,39 @@
+//! Macros for declaring sagas.
+
+/// Declares a saga from pairs of forward and backward steps.
+///
+/// Each step is written as `forward => backward`, a step can be given a
+/// name by putting it in front of the step followed by a colon.
+///
+/// ```
+/// use aud::{saga, Failure, Saga};
+///
+/// fn inc(i: i32) -> Result<i32, Failure<i32>> {
+///     Ok(i + 1)
+/// }
+/// fn dec(i: i32) -> i32 {
+///     i - 1
+/// }
+///
+/// let saga: Saga<i32> = saga![inc => dec, "second": inc => dec];
+/// assert_eq!(saga.tell(0).ok(), Some(2));
+/// assert_eq!(saga.iter().nth(1).and_then(|adventure| adventure.name()), Some("second"));
+/// ```
+#[macro_export]
+macro_rules! saga {
+    (@steps [$($step:expr),*]) => {
+        $crate::Saga::new($crate::__vec![$($step),*])
+    };
+    (@steps [$($step:expr),*] $name:literal : $forward:expr => $backward:expr $(, $($rest:tt)*)?) => {
+        $crate::saga!(@steps [$($step,)* $crate::Adventure::new($forward, $backward).with_name($name)] $($($rest)*)?)
+    };
+    (@steps [$($step:expr),*] $forward:expr => $backward:expr $(, $($rest:tt)*)?) => {
+        $crate::saga!(@steps [$($step,)* $crate::Adventure::new($forward, $backward)] $($($rest)*)?)
+    };
+    ($($steps:tt)*) => {
+        $crate::saga!(@steps [] $($steps)*)
+    };
+}
+
+#[doc(hidden)]
+pub use alloc::vec as __vec;
@@ -0,0 +1,39 @@
+//! Hooks to observe a saga while it is told.
+
+use alloc::boxed::Box;
+use core::error::Error;
+
+/// An observer is notified about every step a saga takes, it can be
+/// used to wire up logging, tracing or metrics without touching the
+/// individual steps.
+///
+/// All methods have empty default implementations so only the events
+/// of interest need to be implemented.
+pub trait Observer<E = Box<dyn Error>> {
+    /// Called before the forward step of the adventure at `index` runs.
+    fn on_forward_start(&mut self, _index: usize) {}
+    /// Called after the forward step of the adventure at `index` succeeded.
+    fn on_forward_success(&mut self, _index: usize) {}
+    /// Called after the forward step of the adventure at `index` failed.
+    fn on_forward_failure(&mut self, _index: usize, _error: &E) {}
+    /// Called when the saga enters a phase, before the forward or the
+    /// backward step of the first adventure of the phase at `index`
+    /// runs. During a rollback the phases are entered in reverse.
+    fn on_phase(&mut self, _index: usize, _phase: &str) {}
+    /// Called before the backward step of the adventure at `index` runs,
+    /// once `before_backward` let the rollback go on.
+    fn on_backward(&mut self, _index: usize) {}
+    /// Called first when the rollback reaches the adventure at `index`,
+    /// returning `Err(AbortRollback)` stops the rollback so neither this
+    /// adventure nor the ones before it are reverted and `on_backward`
+    /// is not called for it.
+    fn before_backward(&mut self, _index: usize) -> Result<(), AbortRollback> {
+        Ok(())
+    }
+}
+
+/// Returned by an observer to stop a rollback, for failures where going
+/// on with the compensations would do more harm than good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbortRollback;
+
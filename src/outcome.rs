@@ -0,0 +1,217 @@
+//! Outcomes of telling a saga that tell a clean rollback apart from a
+//! stalled one.
+
+use crate::{CompensationFailure, Failure};
+use alloc::boxed::Box;
+use core::error::Error;
+use core::fmt;
+
+/// The outcome of telling a saga.
+#[derive(Debug, PartialEq)]
+pub enum SagaOutcome<T, E = Box<dyn Error>> {
+    /// Every adventure was told
+    Completed(T),
+    /// An adventure failed and all told adventures were reverted
+    RolledBack {
+        /// The error of the failed forward step
+        error: E,
+        /// The accumulator after the rollback
+        acc: T,
+        /// The index of the adventure whose forward step failed
+        failed_at: usize,
+    },
+    /// An adventure failed and the rollback stalled at a failing
+    /// backward step, the adventures before it were not reverted
+    RollbackFailed {
+        /// The error of the failed forward step
+        original: E,
+        /// The error of the failed backward step
+        compensation_error: E,
+        /// The index of the adventure whose backward step failed
+        stalled_at: usize,
+        /// The state returned by the failed backward step
+        acc: T,
+    },
+    /// An adventure failed and an observer aborted the rollback, the
+    /// adventures from the abort on backwards were not reverted
+    RollbackAborted {
+        /// The error of the failed forward step
+        error: E,
+        /// The index of the adventure at which the rollback was aborted
+        aborted_at: usize,
+        /// The accumulator when the rollback was aborted
+        acc: T,
+    },
+}
+
+impl<T, E> From<Result<T, Failure<T, E>>> for SagaOutcome<T, E> {
+    fn from(res: Result<T, Failure<T, E>>) -> Self {
+        match res {
+            Ok(acc) => SagaOutcome::Completed(acc),
+            Err(Failure {
+                error,
+                state,
+                failed_at,
+                compensations,
+                aborted_at: None,
+                ..
+            }) if compensations.is_empty() => SagaOutcome::RolledBack {
+                error,
+                acc: state,
+                failed_at,
+            },
+            Err(Failure {
+                error,
+                state,
+                compensations,
+                aborted_at,
+                ..
+            }) => match CompensationFailure::reported(compensations) {
+                Some(compensation) => SagaOutcome::RollbackFailed {
+                    original: error,
+                    compensation_error: compensation.error,
+                    stalled_at: compensation.index,
+                    acc: state,
+                },
+                None => SagaOutcome::RollbackAborted {
+                    error,
+                    aborted_at: aborted_at.unwrap_or_default(),
+                    acc: state,
+                },
+            },
+        }
+    }
+}
+
+/// The result of telling a saga with methods to query whether it
+/// committed, for simple branching.
+pub struct SagaResult<T, E = Box<dyn Error>> {
+    res: Result<T, Failure<T, E>>,
+}
+
+impl<T, E> SagaResult<T, E> {
+    /// Whether every adventure was told
+    pub fn committed(&self) -> bool {
+        self.res.is_ok()
+    }
+    /// Whether an adventure failed and the saga was rolled back, this
+    /// is also true when the rollback stalled or was aborted
+    pub fn rolled_back(&self) -> bool {
+        self.res.is_err()
+    }
+    /// The result of the saga if it committed
+    pub fn value(&self) -> Option<&T> {
+        self.res.as_ref().ok()
+    }
+    /// The failure of the saga if it was rolled back
+    pub fn failure(&self) -> Option<&Failure<T, E>> {
+        self.res.as_ref().err()
+    }
+    /// Turns the status back into the result of telling the saga
+    pub fn into_result(self) -> Result<T, Failure<T, E>> {
+        self.res
+    }
+}
+
+impl<T> SagaResult<T> {
+    /// The error that caused the saga to roll back
+    pub fn error(&self) -> Option<&dyn Error> {
+        self.failure().map(|failure| failure.error().as_ref())
+    }
+}
+
+impl<T, E> From<Result<T, Failure<T, E>>> for SagaResult<T, E> {
+    fn from(res: Result<T, Failure<T, E>>) -> Self {
+        SagaResult { res }
+    }
+}
+
+impl<T: fmt::Debug, E: fmt::Display> fmt::Debug for SagaResult<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SagaResult").field("res", &self.res).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc, inc2};
+    use crate::AbortRollback;
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Observer;
+    use crate::Saga;
+    use crate::SagaOutcome;
+
+    fn refuse(i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        Err(Failure::new(i, "refused"))
+    }
+    #[test]
+    fn completed() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        assert_eq!(saga.tell_outcome(0), SagaOutcome::Completed(2));
+    }
+    #[test]
+    fn rolled_back() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        assert_eq!(
+            saga.tell_outcome(1),
+            SagaOutcome::RolledBack {
+                error: "too big",
+                acc: 1,
+                failed_at: 1,
+            }
+        );
+    }
+    #[test]
+    fn status() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let status = saga.tell_status(0);
+        assert!(status.committed());
+        assert_eq!(status.value(), Some(&2));
+        let status = saga.tell_status(1);
+        assert!(status.rolled_back());
+        assert_eq!(status.value(), None);
+        assert_eq!(status.failure().map(|failure| *failure.error()), Some("too big"));
+        let saga: Saga<i32> = Saga::new(vec![Adventure::new(
+            |i| Err(Failure::new(i, "boxed".into())),
+            dec,
+        )]);
+        assert_eq!(saga.tell_status(0).error().map(|error| error.to_string()), Some("boxed".into()));
+    }
+    #[test]
+    fn rollback_aborted() {
+        struct Abort;
+        impl Observer<&'static str> for Abort {
+            fn before_backward(&mut self, _index: usize) -> Result<(), AbortRollback> {
+                Err(AbortRollback)
+            }
+        }
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let res = saga.tell_with_observer(1, &mut Abort);
+        assert_eq!(
+            SagaOutcome::from(res),
+            SagaOutcome::RollbackAborted {
+                error: "too big",
+                aborted_at: 1,
+                acc: 3,
+            }
+        );
+    }
+    #[test]
+    fn rollback_failed() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::fallible(inc, refuse),
+            Adventure::new(inc2, dec),
+        ]);
+        assert_eq!(
+            saga.tell_outcome(0),
+            SagaOutcome::RollbackFailed {
+                original: "too big",
+                compensation_error: "refused",
+                stalled_at: 1,
+                acc: 2,
+            }
+        );
+    }
+}
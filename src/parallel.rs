@@ -0,0 +1,348 @@
+//! Stages of independent adventures that are told concurrently.
+//!
+//! A parallel stage hands a copy of the accumulator to each of its
+//! branches and tells all of them at once, each on its own thread. When
+//! every branch succeeds their results are merged into the accumulator
+//! for the next adventure. When a branch fails, every branch of the
+//! stage is compensated before the saga rolls back the adventures told
+//! before the stage.
+//!
+//! Compensations always run one after another in reverse branch order,
+//! so a rollback is deterministic no matter in which order the branches
+//! finished.
+//!
+//! A stage with many branches can be limited to a number of worker
+//! threads, each worker takes the next branch that was not told yet
+//! once it is done with its current one.
+
+use crate::adventure::{Backward, Forward, Token};
+use crate::{Adventure, Failure};
+use std::error::Error;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+type BranchForward<T, E> = Box<dyn Fn(T) -> Result<T, Failure<T, E>> + Send + Sync>;
+type BranchBackward<T> = Box<dyn Fn(T) -> T + Send + Sync>;
+type Merge<T> = Box<dyn Fn(Vec<T>) -> T + Send + Sync>;
+
+/// A single branch of a parallel stage.
+struct Branch<T, E> {
+    forward: BranchForward<T, E>,
+    backward: BranchBackward<T>,
+}
+
+/// A group of independent adventures that are told concurrently.
+///
+/// Since the branches run on their own threads the accumulator and the
+/// error type have to be `Send`, the default `Box<dyn Error>` is not so
+/// `Box<dyn Error + Send + Sync>` can be used instead.
+pub struct ParallelStage<T, E = Box<dyn Error>> {
+    branches: Vec<Branch<T, E>>,
+    merge: Merge<T>,
+    /// The number of branches told at the same time
+    max_concurrency: Option<usize>,
+}
+
+/// What a parallel stage needs to compensate its branches later on.
+struct StageToken<T> {
+    input: T,
+    outputs: Vec<T>,
+}
+
+impl<T, E> ParallelStage<T, E> {
+    /// Creates a stage without branches, `merge` combines the results of
+    /// all branches, in the order they were added, into one accumulator.
+    pub fn new<M>(merge: M) -> Self
+    where
+        M: Fn(Vec<T>) -> T + Send + Sync + 'static,
+    {
+        ParallelStage {
+            branches: Vec::new(),
+            merge: Box::new(merge),
+            max_concurrency: None,
+        }
+    }
+    /// Tells at most `n` branches at the same time on as many threads,
+    /// by default every branch gets a thread of its own.
+    ///
+    /// The branches are started in the order they were added whenever a
+    /// thread is free. Compensations run one after another anyway so
+    /// they stay within any limit. A limit of 0 is treated as 1.
+    pub fn with_max_concurrency(mut self, n: usize) -> Self {
+        self.max_concurrency = Some(n.max(1));
+        self
+    }
+    /// Adds a branch with a forward and backward step
+    pub fn branch<F, B>(mut self, forward: F, backward: B) -> Self
+    where
+        F: Fn(T) -> Result<T, Failure<T, E>> + Send + Sync + 'static,
+        B: Fn(T) -> T + Send + Sync + 'static,
+    {
+        self.branches.push(Branch {
+            forward: Box::new(forward),
+            backward: Box::new(backward),
+        });
+        self
+    }
+    /// The number of branches in the stage
+    pub fn len(&self) -> usize {
+        self.branches.len()
+    }
+    /// Whether the stage has no branches
+    pub fn is_empty(&self) -> bool {
+        self.branches.is_empty()
+    }
+    fn tell(&self, acc: T) -> Result<(T, StageToken<T>), Failure<T, E>>
+    where
+        T: Clone + Send,
+        E: Send,
+    {
+        let workers = self
+            .max_concurrency
+            .unwrap_or(self.branches.len())
+            .min(self.branches.len());
+        let next = &AtomicUsize::new(0);
+        let told: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    let acc = acc.clone();
+                    scope.spawn(move || {
+                        let mut told = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            match self.branches.get(index) {
+                                Some(branch) => told.push((index, (branch.forward)(acc.clone()))),
+                                None => return told,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join()).collect()
+        });
+        let mut told: Vec<_> = told
+            .into_iter()
+            .flat_map(|res| res.unwrap_or_else(|payload| panic::resume_unwind(payload)))
+            .collect();
+        told.sort_by_key(|(index, _)| *index);
+        let results: Vec<_> = told.into_iter().map(|(_, res)| res).collect();
+        if results.iter().all(Result::is_ok) {
+            let outputs: Vec<_> = results.into_iter().filter_map(Result::ok).collect();
+            let merged = (self.merge)(outputs.clone());
+            return Ok((
+                merged,
+                StageToken {
+                    input: acc,
+                    outputs,
+                },
+            ));
+        }
+        let mut error = None;
+        for (branch, res) in self.branches.iter().zip(results).rev() {
+            match res {
+                Ok(out) => {
+                    (branch.backward)(out);
+                }
+                Err(Failure {
+                    state, error: e, ..
+                }) => {
+                    (branch.backward)(state);
+                    error = Some(e);
+                }
+            }
+        }
+        // the loop runs in reverse so this is the error of the first
+        // branch that failed
+        let error = error.expect("a branch failed");
+        Err(Failure::new(acc, error))
+    }
+    fn revert(&self, token: StageToken<T>) -> T {
+        for (branch, out) in self.branches.iter().zip(token.outputs).rev() {
+            (branch.backward)(out);
+        }
+        token.input
+    }
+}
+
+impl<T, E> Adventure<T, E>
+where
+    T: Clone + Send + 'static,
+    E: Send + 'static,
+{
+    /// Creates an adventure that tells all branches of a parallel stage
+    /// concurrently.
+    ///
+    /// When the saga rolls back after the stage succeeded each branch is
+    /// compensated with its own result and the adventure before the
+    /// stage is handed the accumulator the stage was told with.
+    pub fn parallel(stage: ParallelStage<T, E>) -> Self {
+        let stage = Arc::new(stage);
+        let revert = stage.clone();
+        Adventure::from_steps(
+            Forward::Shared(Arc::new(move |acc| {
+                stage
+                    .tell(acc)
+                    .map(|(acc, token)| (acc, Some(Box::new(token) as Token)))
+            })),
+            Backward::Shared(Arc::new(move |acc, token, _| {
+                match token.and_then(|token| token.downcast::<StageToken<T>>().ok()) {
+                    Some(token) => Ok(revert.revert(*token)),
+                    // the branches of a failed stage are compensated before
+                    // the stage fails
+                    None => Ok(acc),
+                }
+            })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, StupidError};
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::ParallelStage;
+    use crate::Saga;
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    type SendError = Box<dyn Error + Send + Sync>;
+
+    fn branch(
+        stage: ParallelStage<i32, SendError>,
+        log: &Arc<Mutex<Vec<String>>>,
+        n: i32,
+        fail: bool,
+    ) -> ParallelStage<i32, SendError> {
+        let log = log.clone();
+        stage.branch(
+            move |i| {
+                // finish in reverse order to show ordering does not matter
+                thread::sleep(Duration::from_millis(30 - 10 * n as u64));
+                if fail {
+                    Err(Failure::new(
+                        i,
+                        Box::new(StupidError { stupid: true }) as SendError,
+                    ))
+                } else {
+                    Ok(i + n)
+                }
+            },
+            move |i| {
+                log.lock().unwrap().push(format!("undo {}", n));
+                i - n
+            },
+        )
+    }
+    fn sum(outputs: Vec<i32>) -> i32 {
+        outputs.into_iter().sum()
+    }
+    fn inc(i: i32) -> Result<i32, Failure<i32, SendError>> {
+        Ok(i + 1)
+    }
+    fn fail(i: i32) -> Result<i32, Failure<i32, SendError>> {
+        Err(Failure::new(
+            i,
+            Box::new(StupidError { stupid: false }) as SendError,
+        ))
+    }
+    #[test]
+    fn merges_branches() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stage = ParallelStage::new(sum);
+        let stage = branch(
+            branch(branch(stage, &log, 1, false), &log, 2, false),
+            &log,
+            3,
+            false,
+        );
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::parallel(stage)]);
+        match saga.tell(0) {
+            Ok(res) => assert_eq!(res, 9),
+            Err(_) => unimplemented!(),
+        }
+        assert!(log.lock().unwrap().is_empty());
+    }
+    #[test]
+    fn compensates_failed_stage() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stage = ParallelStage::new(sum);
+        let stage = branch(
+            branch(branch(stage, &log, 1, false), &log, 2, true),
+            &log,
+            3,
+            false,
+        );
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::parallel(stage)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, 0);
+                assert_eq!(failure.to_error().to_string(), "is stupid: true");
+            }
+        }
+        assert_eq!(*log.lock().unwrap(), vec!["undo 3", "undo 2", "undo 1"]);
+    }
+    #[test]
+    fn compensates_stage_after_later_failure() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stage = ParallelStage::new(sum);
+        let stage = branch(branch(stage, &log, 1, false), &log, 2, false);
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::parallel(stage),
+            Adventure::new(fail, dec),
+        ]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 0),
+        }
+        assert_eq!(*log.lock().unwrap(), vec!["undo 2", "undo 1"]);
+    }
+    #[test]
+    fn limits_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let mut stage = ParallelStage::new(sum).with_max_concurrency(2);
+        for n in 1..=6 {
+            let running = running.clone();
+            let most = most.clone();
+            stage = stage.branch(
+                move |i| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(i + n)
+                },
+                move |i| i - n,
+            );
+        }
+        let saga: Saga<i32, SendError> = Saga::new(vec![Adventure::parallel(stage)]);
+        assert_eq!(saga.tell(0).ok(), Some(21));
+        assert!(most.load(Ordering::SeqCst) <= 2);
+    }
+    #[test]
+    fn compensates_limited_stage() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stage = ParallelStage::new(sum).with_max_concurrency(1);
+        let stage = branch(
+            branch(branch(stage, &log, 1, false), &log, 2, true),
+            &log,
+            3,
+            false,
+        );
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::parallel(stage)]);
+        match saga.tell(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 0),
+        }
+        assert_eq!(*log.lock().unwrap(), vec!["undo 3", "undo 2", "undo 1"]);
+    }
+}
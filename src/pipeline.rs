@@ -0,0 +1,252 @@
+//! Sagas whose steps change the type of the accumulator.
+//!
+//! A pipeline is started with `Saga::start` and extended with `then`,
+//! each step maps the current type to the next one and its backward
+//! step maps it back. The chain of steps is encoded in the type of the
+//! pipeline so wiring a step to the wrong types does not compile.
+//!
+//! ```
+//! use aud::{Failure, Saga};
+//!
+//! fn parse(s: String) -> Result<u32, Failure<u32>> {
+//!     Ok(s.len() as u32)
+//! }
+//! fn unparse(i: u32) -> String {
+//!     "x".repeat(i as usize)
+//! }
+//! fn double(i: u32) -> Result<u64, Failure<u64>> {
+//!     Ok(u64::from(i) * 2)
+//! }
+//! fn half(i: u64) -> u32 {
+//!     (i / 2) as u32
+//! }
+//!
+//! let pipeline = Saga::start::<String>().then::<u32>(parse, unparse).then::<u64>(double, half);
+//! assert!(pipeline.tell("abc".to_string()).ok() == Some(6));
+//! ```
+
+use crate::{Failure, Saga};
+use alloc::boxed::Box;
+use core::error::Error;
+use core::marker::PhantomData;
+
+/// A single stage of a pipeline, this is either the `Start` of a
+/// pipeline or a step added to it with `then`.
+pub trait Stage<E> {
+    /// The type the pipeline is told with
+    type Input;
+    /// The type produced by this stage
+    type Output;
+    /// Tells all stages up to and including this one
+    fn tell(&self, acc: Self::Input) -> Result<Self::Output, Failure<Self::Input, E>>;
+    /// Reverts all stages up to and including this one
+    fn revert(&self, acc: Self::Output, error: E) -> Failure<Self::Input, E>;
+    /// The number of steps up to and including this one
+    fn len(&self) -> usize;
+    /// Whether there are no steps up to this one
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The beginning of a pipeline, it passes its input through unchanged.
+pub struct Start<A> {
+    input: PhantomData<fn(A) -> A>,
+}
+
+impl<A, E> Stage<E> for Start<A> {
+    type Input = A;
+    type Output = A;
+    fn tell(&self, acc: A) -> Result<A, Failure<A, E>> {
+        Ok(acc)
+    }
+    fn revert(&self, acc: A, error: E) -> Failure<A, E> {
+        Failure::new(acc, error)
+    }
+    fn len(&self) -> usize {
+        0
+    }
+}
+
+/// A step that maps the output of the previous stage to `C`.
+struct Then<P, F, B, C> {
+    previous: P,
+    forward: F,
+    backward: B,
+    output: PhantomData<fn() -> C>,
+}
+
+impl<E, P, F, B, C> Stage<E> for Then<P, F, B, C>
+where
+    P: Stage<E>,
+    F: Fn(P::Output) -> Result<C, Failure<C, E>>,
+    B: Fn(C) -> P::Output,
+{
+    type Input = P::Input;
+    type Output = C;
+    fn tell(&self, acc: Self::Input) -> Result<C, Failure<Self::Input, E>> {
+        let acc = self.previous.tell(acc)?;
+        match (self.forward)(acc) {
+            Ok(acc1) => Ok(acc1),
+            Err(Failure { state, error, .. }) => Err(Failure {
+                failed_at: self.previous.len(),
+                ..self.previous.revert((self.backward)(state), error)
+            }),
+        }
+    }
+    fn revert(&self, acc: C, error: E) -> Failure<Self::Input, E> {
+        self.previous.revert((self.backward)(acc), error)
+    }
+    fn len(&self) -> usize {
+        self.previous.len() + 1
+    }
+}
+
+/// A saga made of steps that each change the type of the accumulator.
+pub struct Pipeline<P, E = Box<dyn Error>> {
+    stage: P,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<E> Saga<(), E> {
+    /// Starts a pipeline that will be told with a value of type `A`
+    pub fn start<A>() -> Pipeline<Start<A>, E> {
+        Pipeline {
+            stage: Start { input: PhantomData },
+            error: PhantomData,
+        }
+    }
+}
+
+impl<P, E> Pipeline<P, E>
+where
+    P: Stage<E>,
+{
+    /// Adds a step turning the current output into a `C`, the
+    /// backward step turns a `C` back into the previous output.
+    ///
+    /// Both steps are checked against the output of the pipeline so far
+    /// and against `C`, like a backward step that does not map back to
+    /// the previous output:
+    ///
+    /// ```compile_fail
+    /// use aud::{Failure, Saga};
+    ///
+    /// fn parse(s: String) -> Result<u32, Failure<u32>> {
+    ///     Ok(s.len() as u32)
+    /// }
+    /// fn unparse(i: u32) -> u64 {
+    ///     u64::from(i)
+    /// }
+    ///
+    /// Saga::start::<String>().then::<u32>(parse, unparse);
+    /// ```
+    ///
+    /// or a forward step that does not take the previous output:
+    ///
+    /// ```compile_fail
+    /// use aud::{Failure, Saga};
+    ///
+    /// fn double(i: u64) -> Result<u64, Failure<u64>> {
+    ///     Ok(i * 2)
+    /// }
+    /// fn half(i: u64) -> u64 {
+    ///     i / 2
+    /// }
+    ///
+    /// Saga::start::<u32>().then::<u64>(double, half);
+    /// ```
+    pub fn then<C>(
+        self,
+        forward: impl Fn(P::Output) -> Result<C, Failure<C, E>>,
+        backward: impl Fn(C) -> P::Output,
+    ) -> Pipeline<impl Stage<E, Input = P::Input, Output = C>, E> {
+        Pipeline {
+            stage: Then {
+                previous: self.stage,
+                forward,
+                backward,
+                output: PhantomData,
+            },
+            error: PhantomData,
+        }
+    }
+    /// Tells the pipeline, reverts on failure and returns either the
+    /// result or error
+    pub fn tell(&self, acc: P::Input) -> Result<P::Output, Failure<P::Input, E>> {
+        self.stage.tell(acc)
+    }
+    /// The number of steps in the pipeline
+    pub fn len(&self) -> usize {
+        self.stage.len()
+    }
+    /// Whether the pipeline has no steps
+    pub fn is_empty(&self) -> bool {
+        self.stage.is_empty()
+    }
+}
+
+#[cfg(test)]
+// the invoices of the tests are large enough to make clippy complain
+#[allow(clippy::result_large_err)]
+mod tests {
+    use crate::fixtures::StupidError;
+    use crate::Failure;
+    use crate::Saga;
+
+    #[derive(Debug, PartialEq)]
+    struct UserId(u32);
+    #[derive(Debug, PartialEq)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+    #[derive(Debug, PartialEq)]
+    struct Invoice {
+        user: User,
+        amount: u32,
+    }
+
+    fn load(id: UserId) -> Result<User, Failure<User>> {
+        Ok(User {
+            id: id.0,
+            name: format!("user {}", id.0),
+        })
+    }
+    fn unload(user: User) -> UserId {
+        UserId(user.id)
+    }
+    fn bill(user: User) -> Result<Invoice, Failure<Invoice>> {
+        let amount = user.id * 10;
+        Ok(Invoice { user, amount })
+    }
+    fn refuse(user: User) -> Result<Invoice, Failure<Invoice>> {
+        let invoice = Invoice { user, amount: 0 };
+        Err(Failure::new(invoice, Box::new(StupidError { stupid: true })))
+    }
+    fn refund(invoice: Invoice) -> User {
+        invoice.user
+    }
+    #[test]
+    fn changes_types() {
+        let pipeline = Saga::start::<UserId>().then(load, unload).then(bill, refund);
+        assert_eq!(pipeline.len(), 2);
+        match pipeline.tell(UserId(4)) {
+            Ok(invoice) => assert_eq!(invoice.amount, 40),
+            Err(_) => unimplemented!(),
+        }
+    }
+    #[test]
+    fn reverts_types() {
+        let pipeline = Saga::start::<UserId>()
+            .then::<User>(load, unload)
+            .then::<Invoice>(refuse, refund);
+        match pipeline.tell(UserId(4)) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, UserId(4));
+            }
+        }
+    }
+}
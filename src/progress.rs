@@ -0,0 +1,92 @@
+//! Progress of telling a saga for driving progress indicators.
+
+use crate::hooks::Hooks;
+use alloc::vec::Vec;
+
+/// Reports progress to a callback after each step.
+pub(crate) struct Progress<F> {
+    callback: F,
+    /// The weight of all adventures before each index, followed by the
+    /// total weight
+    done: Vec<u64>,
+}
+
+impl<F> Progress<F>
+where
+    F: FnMut(u64, u64),
+{
+    pub(crate) fn new<I>(weights: I, callback: F) -> Self
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let mut done = alloc::vec![0];
+        let mut sum = 0;
+        for weight in weights {
+            sum += u64::from(weight);
+            done.push(sum);
+        }
+        Progress { callback, done }
+    }
+    fn report(&mut self, index: usize) {
+        let total = self.done.last().copied().unwrap_or(0);
+        (self.callback)(self.done[index], total);
+    }
+}
+
+impl<T, E, F> Hooks<T, E> for Progress<F>
+where
+    F: FnMut(u64, u64),
+{
+    fn after_forward(&mut self, index: usize, _acc: &T) {
+        self.report(index + 1);
+    }
+    fn after_backward(&mut self, index: usize, _acc: &T) {
+        self.report(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc, inc2};
+    use crate::Adventure;
+    use crate::Saga;
+
+    #[test]
+    fn reports_progress() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut progress = Vec::new();
+        let res = saga.tell_with_progress(-1, |done, total| progress.push((done, total)));
+        assert_eq!(res.ok(), Some(2));
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+    #[test]
+    fn reports_rollback() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut progress = Vec::new();
+        let res = saga.tell_with_progress(0, |done, _| progress.push(done));
+        assert!(res.is_err());
+        assert_eq!(progress, vec![1, 2, 2, 1, 0]);
+    }
+    #[test]
+    fn reports_weight() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec).with_weight(10),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec).with_weight(5),
+        ]);
+        assert_eq!(saga.total_weight(), 16);
+        assert_eq!(saga.remaining_weight(1), 6);
+        let mut progress = Vec::new();
+        let res = saga.tell_with_progress(-1, |done, total| progress.push((done, total)));
+        assert_eq!(res.ok(), Some(2));
+        assert_eq!(progress, vec![(10, 16), (11, 16), (16, 16)]);
+    }
+}
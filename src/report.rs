@@ -0,0 +1,148 @@
+//! Reports on the compensations run while rolling back a saga.
+
+use crate::hooks::Hooks;
+use alloc::vec::Vec;
+
+/// A report of the compensations that ran while a saga was rolled back.
+pub struct RollbackReport<T> {
+    steps: Vec<RollbackStep<T>>,
+}
+
+impl<T> RollbackReport<T> {
+    /// All adventures that were told before the saga failed, in the
+    /// order they were rolled back
+    pub fn steps(&self) -> &[RollbackStep<T>] {
+        &self.steps
+    }
+    /// Whether every told adventure was compensated successfully
+    pub fn complete(&self) -> bool {
+        self.steps.iter().all(|step| step.state.is_some())
+    }
+}
+
+/// The compensation of a single adventure during a rollback.
+pub struct RollbackStep<T> {
+    index: usize,
+    ran: bool,
+    state: Option<T>,
+}
+
+impl<T> RollbackStep<T> {
+    /// The index of the adventure
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Whether the backward step of this adventure ran, this is false
+    /// when the rollback stalled before reaching it
+    pub fn ran(&self) -> bool {
+        self.ran
+    }
+    /// The state the backward step returned, this is `None` when the
+    /// backward step did not run or failed
+    pub fn state(&self) -> Option<&T> {
+        self.state.as_ref()
+    }
+}
+
+/// Builds a rollback report while a saga is told.
+pub(crate) struct Reporter<T> {
+    told: Vec<usize>,
+    steps: Vec<RollbackStep<T>>,
+}
+
+impl<T> Reporter<T> {
+    pub(crate) fn new() -> Self {
+        Reporter {
+            told: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+    pub(crate) fn report(mut self) -> RollbackReport<T> {
+        let mut reverted: Vec<_> = self.steps.iter().map(|step| step.index).collect();
+        reverted.sort_unstable();
+        let pending = self
+            .told
+            .iter()
+            .rev()
+            .filter(|index| reverted.binary_search(index).is_err())
+            .map(|&index| RollbackStep {
+                index,
+                ran: false,
+                state: None,
+            });
+        self.steps.extend(pending);
+        RollbackReport { steps: self.steps }
+    }
+}
+
+impl<T: Clone, E> Hooks<T, E> for Reporter<T> {
+    fn before_forward(&mut self, index: usize, _acc: &T) {
+        self.told.push(index);
+    }
+    fn after_backward(&mut self, index: usize, acc: &T) {
+        self.steps.push(RollbackStep {
+            index,
+            ran: true,
+            state: Some(acc.clone()),
+        });
+    }
+    fn on_backward_failure(&mut self, index: usize, _error: &E) {
+        self.steps.push(RollbackStep {
+            index,
+            ran: true,
+            state: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc2, StupidError};
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Saga;
+
+    fn refuse(i: i32) -> Result<i32, Failure<i32>> {
+        Err(Failure::new(i, Box::new(StupidError { stupid: false })))
+    }
+    #[test]
+    fn reports_full_rollback() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell_with_report(0) {
+            Ok(_) => unimplemented!(),
+            Err((_, report)) => {
+                assert!(report.complete());
+                let steps: Vec<_> = report
+                    .steps()
+                    .iter()
+                    .map(|step| (step.index(), step.ran(), step.state().cloned()))
+                    .collect();
+                assert_eq!(steps, vec![(2, true, Some(2)), (1, true, Some(1)), (0, true, Some(0))]);
+            }
+        }
+    }
+    #[test]
+    fn reports_stalled_rollback() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::fallible(inc2, refuse),
+            Adventure::new(inc2, dec),
+        ]);
+        match saga.tell_with_report(0) {
+            Ok(_) => unimplemented!(),
+            Err((_, report)) => {
+                assert!(!report.complete());
+                let steps: Vec<_> = report
+                    .steps()
+                    .iter()
+                    .map(|step| (step.index(), step.ran(), step.state().cloned()))
+                    .collect();
+                assert_eq!(steps, vec![(2, true, Some(2)), (1, true, None), (0, false, None)]);
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Telling a saga one step at a time.
+//!
+//! Event loops often can not block until a whole saga was told. A runner
+//! tells a single step each time it is stepped, so the caller decides
+//! when the next step runs and can do other work in between. Once a step
+//! failed further calls run the backward steps one at a time in the
+//! same order as any other rollback.
+
+use crate::adventure::Token;
+use crate::failure::Origin;
+use crate::hooks::{Hooks, Limited, Unhooked};
+use crate::saga::{commit, rollback_order, rolled_back, tell_step, undo_step, Journal, Stepped, Undone};
+use crate::{Adventure, Failure, Saga};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+/// What stepping a saga led to.
+pub enum StepOutcome<T, E = Box<dyn Error>> {
+    /// A forward or backward step ran, the saga is not finished yet
+    Running,
+    /// All adventures were told and this is the result
+    Completed(T),
+    /// The saga failed and the rollback is over
+    Failed(Failure<T, E>),
+}
+
+impl<T: fmt::Debug, E: fmt::Display> fmt::Debug for StepOutcome<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StepOutcome::Running => write!(f, "Running"),
+            StepOutcome::Completed(res) => f.debug_tuple("Completed").field(res).finish(),
+            StepOutcome::Failed(failure) => f.debug_tuple("Failed").field(failure).finish(),
+        }
+    }
+}
+
+type Rollback<'a, T, E> = vec::IntoIter<(usize, &'a Adventure<T, E>, Option<Token>)>;
+
+enum State<'a, T, E> {
+    Forward {
+        index: usize,
+        acc: T,
+        told: Journal<&'a Adventure<T, E>>,
+    },
+    Rollback {
+        undone: Undone<T, E>,
+        steps: Rollback<'a, T, E>,
+        error: E,
+        failed_at: usize,
+        origin: Option<Box<Origin>>,
+    },
+    Done,
+}
+
+/// Tells a saga one step each time it is stepped.
+pub struct SagaRunner<'a, T, E = Box<dyn Error>> {
+    saga: &'a Saga<T, E>,
+    /// The number of forward steps told so far
+    told: usize,
+    state: State<'a, T, E>,
+}
+
+impl<T, E> Saga<T, E> {
+    /// Creates a runner that tells the saga with `acc` one step at a
+    /// time, nothing is told before it is stepped
+    pub fn runner(&self, acc: T) -> SagaRunner<'_, T, E> {
+        SagaRunner {
+            saga: self,
+            told: 0,
+            state: State::Forward {
+                index: 0,
+                acc,
+                told: Vec::new(),
+            },
+        }
+    }
+}
+
+impl<T, E> SagaRunner<'_, T, E> {
+    /// Whether the saga failed and is being rolled back
+    pub fn is_rolling_back(&self) -> bool {
+        matches!(self.state, State::Rollback { .. })
+    }
+    /// Whether the saga completed or failed
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+    /// Tells the next adventure of the saga, or while it is rolled back
+    /// runs the next backward step.
+    ///
+    /// Every adventure takes one call, including the ones that are
+    /// skipped. The saga is finished once `Completed` or `Failed` was
+    /// returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the saga is already finished.
+    pub fn step(&mut self) -> StepOutcome<T, E> {
+        let mut unhooked = Unhooked;
+        let mut hooks = Limited::resumed(&mut unhooked, &self.saga.settings, self.told);
+        let adventures = &self.saga.adventures;
+        let outcome = match core::mem::replace(&mut self.state, State::Done) {
+            State::Forward { index, acc, told } if index >= adventures.len() => {
+                if adventures.is_empty() {
+                    if let Some(error) = Hooks::<T, E>::empty(&mut hooks) {
+                        return StepOutcome::Failed(Failure::new(acc, error));
+                    }
+                }
+                StepOutcome::Completed(commit(told, acc))
+            }
+            State::Forward { index, acc, mut told } => {
+                match tell_step(&mut told, index, &adventures[index], &mut hooks, acc) {
+                    Stepped::Next(acc) => {
+                        self.state = State::Forward {
+                            index: index + 1,
+                            acc,
+                            told,
+                        };
+                        StepOutcome::Running
+                    }
+                    Stepped::Completed(acc) => StepOutcome::Completed(commit(told, acc)),
+                    Stepped::Revert {
+                        acc,
+                        error,
+                        failed_at,
+                        origin,
+                    } => {
+                        let last = told.last().map_or(0, |(i, _, _)| *i);
+                        self.state = State::Rollback {
+                            undone: Undone::new(acc),
+                            steps: rollback_order(told, Hooks::<T, E>::rollback_order(&hooks)).into_iter(),
+                            error,
+                            failed_at: failed_at.unwrap_or(last),
+                            origin,
+                        };
+                        StepOutcome::Running
+                    }
+                    Stepped::Stalled(failure) => StepOutcome::Failed(failure),
+                }
+            }
+            State::Rollback {
+                undone,
+                mut steps,
+                error,
+                failed_at,
+                origin,
+            } => {
+                let (undone, go_on) = match steps.next() {
+                    Some((i, step, token)) => undo_step(undone, i, step, token, &mut hooks, &error),
+                    None => (undone, false),
+                };
+                if go_on {
+                    self.state = State::Rollback {
+                        undone,
+                        steps,
+                        error,
+                        failed_at,
+                        origin,
+                    };
+                    StepOutcome::Running
+                } else {
+                    StepOutcome::Failed(Failure {
+                        failed_at,
+                        origin,
+                        ..rolled_back(failed_at, undone, &mut hooks, error)
+                    })
+                }
+            }
+            State::Done => panic!("stepped a saga that is already finished"),
+        };
+        self.told = hooks.told();
+        outcome
+    }
+}
+
+impl<T, E> fmt::Debug for SagaRunner<'_, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SagaRunner")
+            .field("rolling_back", &self.is_rolling_back())
+            .field("finished", &self.is_finished())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc, inc2};
+    use crate::Adventure;
+    use crate::Saga;
+    use crate::StepOutcome;
+
+    #[test]
+    fn steps_forward() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc, dec)]);
+        let mut runner = saga.runner(0);
+        assert!(matches!(runner.step(), StepOutcome::Running));
+        assert!(matches!(runner.step(), StepOutcome::Running));
+        assert!(matches!(runner.step(), StepOutcome::Completed(2)));
+        assert!(runner.is_finished());
+    }
+    #[test]
+    fn steps_rollback() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut runner = saga.runner(0);
+        let mut steps = 0;
+        let failure = loop {
+            match runner.step() {
+                StepOutcome::Running => steps += 1,
+                StepOutcome::Completed(_) => unimplemented!(),
+                StepOutcome::Failed(failure) => break failure,
+            }
+            if steps == 3 {
+                assert!(runner.is_rolling_back());
+            }
+        };
+        // three forward steps and three backward steps
+        assert_eq!(steps, 6);
+        assert_eq!(failure.failed_at(), 2);
+        assert_eq!(failure.rolled_back(), 3);
+        assert_eq!(failure.state, 0);
+    }
+    #[test]
+    fn steps_like_tell() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec).with_name("second"),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let mut runner = saga.runner(0);
+        let failure = loop {
+            if let StepOutcome::Failed(failure) = runner.step() {
+                break failure;
+            }
+        };
+        let told = saga.tell(0).unwrap_err();
+        assert_eq!(failure.failed_at(), told.failed_at());
+        assert_eq!(failure.step_name(), told.step_name());
+        assert_eq!(failure.state, told.state);
+    }
+}
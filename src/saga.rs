@@ -0,0 +1,1208 @@
+//! Sagas and the machinery to tell and revert them.
+
+use crate::adventure::{same, Completed, ErrorContext, InPlace, Reason, Token, Told};
+use crate::checkpoint::Checkpoints;
+use crate::failure::Origin;
+use crate::hooks::{
+    Attempts, BestEffort, Cancellation, Defaulted, Hooks, Inspect, Inspected, Limited, Observed,
+    Persisted, Settings, Snapshots, StepLimit, Unhooked,
+};
+use crate::progress::Progress;
+use crate::report::{Reporter, RollbackReport};
+use crate::state::SagaState;
+use crate::trace::Tracer;
+#[cfg(feature = "std")]
+use crate::timing::{Deadline, StepTiming, Timer};
+#[cfg(feature = "std")]
+use crate::DeadlineExceeded;
+#[cfg(feature = "std")]
+use std::time::Instant;
+use crate::{
+    Adventure, Cancelled, ChaosPolicy, CompArtifact, CompensationFailure, EmptySaga, Failure,
+    InverseCheck, Observer, SagaEvent, SagaOutcome, SagaResult, StepLimitExceeded, Warning,
+};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::cmp::Reverse;
+use core::fmt;
+use core::ops::Range;
+use core::sync::atomic::AtomicBool;
+
+/// The attempts of each forward step by the index of its adventure.
+type Counted = Vec<(usize, u32)>;
+/// A forward and backward step as plain function pointers.
+type Pair<T, E> = (fn(T) -> Result<T, Failure<T, E>>, fn(T) -> T);
+
+/// The order in which the told adventures of a saga are reverted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RollbackOrder {
+    /// The last told adventure is reverted first
+    #[default]
+    Reverse,
+    /// The first told adventure is reverted first, like for releasing
+    /// locks in the order they were acquired
+    Forward,
+}
+
+/// A sage of many adventures that can be told.
+///
+/// The error type `E` defaults to `Box<dyn Error>` but any type can be
+/// used, which allows matching on a concrete error after telling.
+///
+/// # Thread safety
+///
+/// A saga and its adventures are `Send` and `Sync` whatever `T` and `E`
+/// are, so a saga can be put in an `Arc` and told from many threads at
+/// once. This is why the steps have to be `Send + Sync + 'static`:
+///
+/// * forward and backward steps, including single use ones from
+///   `Adventure::once`, validators, predicates and the callbacks given to
+///   `Saga::on_rollback` and `Saga::with_default_backward` need
+///   `Send + Sync`,
+/// * steps from `Adventure::new_mut` and compensations registered with
+///   `Compensations::register_compensation` only need `Send`, they are
+///   called through a lock or only once,
+/// * the data a forward step hands to its compensation in
+///   `Adventure::with_compensation` needs `Send`.
+///
+/// The accumulator and the error are not part of the saga, to hand a
+/// result to another thread they have to be `Send` on their own, for
+/// example with `Box<dyn Error + Send + Sync>` as the error.
+pub struct Saga<T, E = Box<dyn Error>> {
+    pub(crate) adventures: Vec<Adventure<T, E>>,
+    pub(crate) settings: Settings<T, E>,
+}
+
+impl<T, E> Saga<T, E> {
+    /// Creates a new saga from a vector of adventures
+    pub fn new(adventures: Vec<Adventure<T, E>>) -> Self {
+        Saga {
+            adventures,
+            settings: Settings::new(),
+        }
+    }
+    /// Creates a saga without adventures, telling it hands the
+    /// accumulator back unchanged.
+    ///
+    /// The empty saga is the identity of `Saga::then`, chaining it before
+    /// or after another saga yields that saga.
+    pub const fn empty() -> Self {
+        Saga {
+            adventures: Vec::new(),
+            settings: Settings::new(),
+        }
+    }
+    /// Creates a saga from pairs of forward and backward steps, one
+    /// adventure for each pair
+    pub fn from_pairs(pairs: &[Pair<T, E>]) -> Self
+    where
+        T: 'static,
+        E: 'static,
+    {
+        let adventures = pairs
+            .iter()
+            .map(|&(forward, backward)| Adventure::from_fns(forward, backward))
+            .collect();
+        Saga::new(adventures)
+    }
+    /// Builds a saga from pairs of forward and backward steps and tells
+    /// it with `init` right away, for simple sagas that are told once
+    pub fn fold<I>(init: T, steps: I) -> Result<T, Failure<T, E>>
+    where
+        T: 'static,
+        E: 'static,
+        I: IntoIterator<Item = Pair<T, E>>,
+    {
+        let adventures = steps
+            .into_iter()
+            .map(|(forward, backward)| Adventure::from_fns(forward, backward))
+            .collect();
+        Saga::new(adventures).tell(init)
+    }
+    /// Limits the number of forward steps a single tell of the saga may
+    /// run, a safety valve against sagas that keep growing.
+    ///
+    /// Once the limit is reached the next forward step is not run, the
+    /// forward steps told so far are rolled back and the failure holds a
+    /// `StepLimitExceeded` error. Skipped adventures do not count. The
+    /// adventures added by an expanding adventure and those of nested
+    /// sagas are told as part of their adventure and count as one step,
+    /// `tell_each` applies no limit.
+    pub fn with_max_steps(mut self, limit: usize) -> Self
+    where
+        E: From<StepLimitExceeded>,
+    {
+        self.settings.limit = Some(StepLimit {
+            max: limit,
+            error: E::from,
+        });
+        self
+    }
+    /// Makes telling the saga fail with an `EmptySaga` error when it has
+    /// no adventures, by default an empty saga hands the accumulator
+    /// back unchanged.
+    ///
+    /// The failure holds the accumulator the saga was told with. Resuming
+    /// a saga with `tell_from` and `tell_each` are not affected.
+    pub fn strict(mut self) -> Self
+    where
+        E: From<EmptySaga>,
+    {
+        self.settings.strict = Some(E::from);
+        self
+    }
+    /// Registers a callback that is called whenever the saga was rolled
+    /// back, however it was told, like for counting rollbacks in a
+    /// process wide metric.
+    ///
+    /// The callback is handed the index of the adventure that failed,
+    /// the error and the number of adventures that were compensated. It
+    /// is called once the rollback is over, also when it stalled or was
+    /// aborted. Rollbacks of nested sagas and of `tell_each` are not
+    /// reported.
+    pub fn on_rollback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(usize, &E, usize) + Send + Sync + 'static,
+    {
+        self.settings.on_rollback = Some(Arc::new(callback));
+        self
+    }
+    /// Sets the order in which the told adventures are reverted, by
+    /// default the last told adventure is reverted first.
+    ///
+    /// Adventures with a rollback priority are still reverted before
+    /// those with a lower one, the order only applies to adventures of
+    /// the same priority. Nested sagas and `tell_each` always revert in
+    /// reverse order.
+    pub fn with_rollback_order(mut self, order: RollbackOrder) -> Self {
+        self.settings.order = order;
+        self
+    }
+    /// Sets the backward step of the adventures created with
+    /// `Adventure::forward_only`.
+    ///
+    /// An adventure with a backward step of its own always runs that
+    /// one, the default only stands in for adventures that have none.
+    /// Nested sagas use their own default.
+    pub fn with_default_backward<F>(mut self, backward: F) -> Self
+    where
+        F: Fn(T) -> T + Send + Sync + 'static,
+    {
+        self.settings.default_backward = Some(Arc::new(backward));
+        self
+    }
+    /// Adds adventures to the end of the saga grouped under a phase, like
+    /// `reserve` or `charge`.
+    ///
+    /// Observers are told when the saga enters the phase while telling
+    /// or rolling it back, and failures and failed compensations report
+    /// the phase of their adventure. Adventures of a phase are still
+    /// told and reverted one by one.
+    pub fn phase<S>(mut self, name: S, adventures: Vec<Adventure<T, E>>) -> Self
+    where
+        S: Into<String>,
+    {
+        let name: Arc<str> = Arc::from(name.into());
+        self.adventures
+            .extend(adventures.into_iter().map(|adventure| adventure.in_phase(name.clone())));
+        self
+    }
+    /// The phases of the saga along with the indices of their
+    /// adventures, in the order they are told. Adventures that are not
+    /// part of a phase are left out.
+    pub fn phases(&self) -> Vec<(&str, Range<usize>)> {
+        let mut phases: Vec<(&str, Range<usize>)> = Vec::new();
+        for (i, adventure) in self.adventures.iter().enumerate() {
+            let phase = match adventure.phase() {
+                Some(phase) => phase,
+                None => continue,
+            };
+            match phases.last_mut() {
+                Some((last, range)) if *last == phase && range.end == i => range.end += 1,
+                _ => phases.push((phase, i..i + 1)),
+            }
+        }
+        phases
+    }
+    /// The indices of the adventures tagged with `key` set to `value`,
+    /// the adventures of nested sagas are not searched.
+    pub fn steps_with_tag(&self, key: &str, value: &str) -> Vec<usize> {
+        self.adventures
+            .iter()
+            .enumerate()
+            .filter(|(_, adventure)| adventure.tags().get(key).map(String::as_str) == Some(value))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// Adds an adventure to the end of the saga, it is told after all
+    /// adventures already in the saga and reverted before them.
+    pub fn push(&mut self, adventure: Adventure<T, E>) {
+        self.adventures.push(adventure);
+    }
+    /// Chains two sagas, the adventures of `other` are told after the
+    /// adventures of this saga.
+    ///
+    /// The combined saga is one transaction, when an adventure of
+    /// `other` fails the adventures of this saga are reverted as well.
+    /// Indices continue across the boundary, so the first adventure of
+    /// `other` has the index `self.len()`.
+    pub fn then(mut self, other: Saga<T, E>) -> Self {
+        self.adventures.extend(other.adventures);
+        self
+    }
+    /// The number of adventures in the saga
+    pub fn len(&self) -> usize {
+        self.adventures.len()
+    }
+    /// Whether the saga has no adventures
+    pub fn is_empty(&self) -> bool {
+        self.adventures.is_empty()
+    }
+    /// The weight of all adventures in the saga
+    pub fn total_weight(&self) -> u64 {
+        self.remaining_weight(0)
+    }
+    /// The weight of the adventures from `index` on, that is the work
+    /// left once the adventures before it were told
+    pub fn remaining_weight(&self, index: usize) -> u64 {
+        self.adventures
+            .iter()
+            .skip(index)
+            .map(|adventure| u64::from(adventure.weight()))
+            .sum()
+    }
+    /// Turns the saga back into its adventures in the order they are
+    /// told
+    pub fn into_adventures(self) -> Vec<Adventure<T, E>> {
+        self.adventures
+    }
+    /// The adventures of the saga, they can be reordered, filtered or
+    /// added to before the saga is told
+    pub fn adventures_mut(&mut self) -> &mut Vec<Adventure<T, E>> {
+        &mut self.adventures
+    }
+    /// Renders the plan of the saga like the diagram in the crate docs,
+    /// the forward steps from left to right on the first line and the
+    /// backward steps from right to left below.
+    ///
+    /// Adventures are shown by their name, unnamed ones by their index.
+    /// An empty saga renders as an empty string.
+    pub fn render_diagram(&self) -> String {
+        let names: Vec<String> = self
+            .adventures
+            .iter()
+            .enumerate()
+            .map(|(i, adventure)| match adventure.name() {
+                Some(name) => String::from(name),
+                None => i.to_string(),
+            })
+            .collect();
+        if names.is_empty() {
+            return String::new();
+        }
+        let mut diagram = names.join(" -> ");
+        diagram.push('\n');
+        diagram.push_str(&names.join(" <- "));
+        diagram
+    }
+    /// Iterates over the adventures in the order they are told
+    pub fn iter(&self) -> core::slice::Iter<'_, Adventure<T, E>> {
+        self.adventures.iter()
+    }
+    /// Tells a saga, reverts on failure and returns either the result or error
+    ///
+    /// # Panics
+    ///
+    /// Panics when the saga contains single use adventures created with
+    /// `Adventure::once`, those sagas have to be told with `tell_owned`.
+    pub fn tell(&self, acc: T) -> Result<T, Failure<T, E>> {
+        tell_(&self.adventures, &mut Limited::new(&mut Unhooked, &self.settings), acc)
+    }
+    /// Tells a saga like `tell` while notifying the observer about each
+    /// forward and backward step
+    pub fn tell_with_observer<O>(&self, acc: T, observer: &mut O) -> Result<T, Failure<T, E>>
+    where
+        O: Observer<E> + ?Sized,
+    {
+        let mut observed = Observed::new(observer, self.adventures.iter().map(Adventure::phase).collect());
+        tell_(&self.adventures, &mut Limited::new(&mut observed, &self.settings), acc)
+    }
+    /// Tells a saga like `tell`, on failure a report of the rollback is
+    /// returned next to the failure
+    pub fn tell_with_report(&self, acc: T) -> Result<T, (Failure<T, E>, RollbackReport<T>)>
+    where
+        T: Clone,
+    {
+        let mut reporter = Reporter::new();
+        tell_(&self.adventures, &mut Limited::new(&mut reporter, &self.settings), acc).map_err(|failure| (failure, reporter.report()))
+    }
+    /// Tells a saga like `tell` and records how often each forward step
+    /// was attempted, as pairs of the index of the adventure and the
+    /// number of attempts in the order they were told. They are returned
+    /// next to the result and on failure attached to it, see
+    /// `Failure::attempts`.
+    pub fn tell_with_attempts(&self, acc: T) -> Result<(T, Counted), Failure<T, E>> {
+        let mut attempts = Attempts(Vec::new());
+        match tell_(&self.adventures, &mut Limited::new(&mut attempts, &self.settings), acc) {
+            Ok(acc) => Ok((acc, attempts.0)),
+            Err(mut failure) => {
+                failure.details_mut().attempts = attempts.0;
+                Err(failure)
+            }
+        }
+    }
+    /// Tells a saga like `tell` and snapshots the accumulator after each
+    /// forward step that succeeded.
+    ///
+    /// On rollback every backward step is handed the snapshot of its own
+    /// adventure instead of what the later backward steps returned, so
+    /// a backward step sees the exact state its forward step left. The
+    /// adventure that failed is handed the state of its failure, and
+    /// adventures without a snapshot, like ones that were committed before,
+    /// what the backward step before them returned.
+    pub fn tell_snapshotted(&self, acc: T) -> Result<T, Failure<T, E>>
+    where
+        T: Clone,
+    {
+        tell_(&self.adventures, &mut Limited::new(&mut Snapshots::new(), &self.settings), acc)
+    }
+    /// Tells a saga like `tell` and returns the warnings of the adventures
+    /// created with `Adventure::with_warnings` next to the result, in the
+    /// order they were told. Warnings of nested sagas are dropped.
+    pub fn tell_with_warnings(&self, acc: T) -> Result<(T, Vec<Warning>), Failure<T, E>> {
+        let (acc, mut told) = run_(&self.adventures, &mut Limited::new(&mut Unhooked, &self.settings), acc)?;
+        let mut warnings = Vec::new();
+        for (i, _, token) in told.iter_mut() {
+            if token.as_ref().is_some_and(|token| token.is::<Vec<Warning>>()) {
+                if let Some(Ok(told)) = token.take().map(|token| token.downcast::<Vec<Warning>>()) {
+                    for mut warning in *told {
+                        warning.index = *i;
+                        warnings.push(warning);
+                    }
+                }
+            }
+        }
+        Ok((commit(told, acc), warnings))
+    }
+    /// Tells a saga like `tell` but makes the forward steps fail that
+    /// `chaos` has a fault for, to test the rollback
+    pub fn tell_with_chaos(&self, acc: T, chaos: ChaosPolicy<E>) -> Result<T, Failure<T, E>> {
+        let mut chaos = chaos;
+        tell_(&self.adventures, &mut Limited::new(&mut chaos, &self.settings), acc)
+    }
+    /// Tells a saga like `tell` but on failure only rolls it back to the
+    /// last checkpoint that was reached, see `Adventure::with_checkpoint`.
+    ///
+    /// A checkpoint is reached once the forward step of its adventure
+    /// succeeded. The adventures up to and including that one are not
+    /// compensated, so the state of the failure is the accumulator as of
+    /// the checkpoint after the adventures told after it were reverted.
+    /// Their commit steps do not run either since the saga failed. The
+    /// name of the checkpoint is returned next to the failure, when no
+    /// checkpoint was reached the saga is rolled back fully and it is
+    /// `None`. Checkpoints of nested sagas are ignored.
+    pub fn tell_checkpointed(&self, acc: T) -> Result<T, (Failure<T, E>, Option<&str>)> {
+        let mut checkpoints = Checkpoints::new(self.adventures.iter().map(Adventure::checkpoint).collect());
+        let res = tell_(&self.adventures, &mut Limited::new(&mut checkpoints, &self.settings), acc);
+        res.map_err(|failure| (failure, checkpoints.reached()))
+    }
+    /// Tells a saga like `tell` but returns an outcome that tells a
+    /// complete rollback apart from one that stalled
+    pub fn tell_outcome(&self, acc: T) -> SagaOutcome<T, E> {
+        self.tell(acc).into()
+    }
+    /// Tells a saga like `tell` but returns a status that can be asked
+    /// whether the saga committed or was rolled back
+    pub fn tell_status(&self, acc: T) -> SagaResult<T, E> {
+        self.tell(acc).into()
+    }
+    /// Tells a saga like `tell` but keeps rolling back past failing
+    /// backward steps so as much as possible is compensated.
+    ///
+    /// Every failed compensation is collected in the failure, see
+    /// `Failure::compensation_failures`. Each backward step after a
+    /// failed one is handed the state the failed step returned.
+    pub fn tell_best_effort(&self, acc: T) -> Result<T, Failure<T, E>> {
+        tell_(&self.adventures, &mut Limited::new(&mut BestEffort, &self.settings), acc)
+    }
+    /// Tells a saga like `tell` while measuring how long each forward
+    /// and backward step takes, the timings are in the order the steps
+    /// ran.
+    #[cfg(feature = "std")]
+    pub fn tell_timed(&self, acc: T) -> (Result<T, Failure<T, E>>, Vec<StepTiming>) {
+        let mut timer = Timer::new();
+        let res = tell_(&self.adventures, &mut Limited::new(&mut timer, &self.settings), acc);
+        (res, timer.timings())
+    }
+    /// Tells a saga like `tell` handing the index of each adventure and
+    /// the accumulator it returned to `persist`, like for writing the
+    /// intermediate states to an audit log.
+    ///
+    /// `persist` is called after every forward step that succeeded. When
+    /// it fails the saga is rolled back including the adventure whose
+    /// state could not be persisted, the failure then holds the error of
+    /// `persist` and `failed_at` is the index of that adventure. The
+    /// adventures of a nested saga count as a single step.
+    pub fn tell_persisted<P>(&self, acc: T, persist: P) -> Result<T, Failure<T, E>>
+    where
+        P: FnMut(usize, &T) -> Result<(), Box<dyn Error>>,
+        E: From<Box<dyn Error>>,
+    {
+        tell_(&self.adventures, &mut Limited::new(&mut Persisted(persist), &self.settings), acc)
+    }
+    /// Tells a saga like `tell` calling `progress` with the weight of
+    /// the completed adventures and the total weight after each forward
+    /// step that succeeded. With the default weight of 1 these are the
+    /// number of completed adventures and of all adventures.
+    ///
+    /// Conditional adventures that are skipped count as completed once an
+    /// adventure after them was told. During a rollback the progress goes
+    /// down again, after the backward step of an adventure the callback
+    /// is handed the weight of the adventures before it.
+    pub fn tell_with_progress<F>(&self, acc: T, progress: F) -> Result<T, Failure<T, E>>
+    where
+        F: FnMut(u64, u64),
+    {
+        let weights = self.adventures.iter().map(|adventure| adventure.weight());
+        let mut progress = Progress::new(weights, progress);
+        tell_(&self.adventures, &mut Limited::new(&mut progress, &self.settings), acc)
+    }
+    /// Tells a saga like `tell` handing the index of each adventure and
+    /// the accumulator it returned to `inspect`, a lighter way than an
+    /// `Observer` to look at the state between steps.
+    ///
+    /// `inspect` is called after every forward step that succeeded and
+    /// can not change how the saga is told, with `None` this is the same
+    /// as `tell`.
+    pub fn tell_inspect(&self, acc: T, inspect: Option<Inspect<'_, T>>) -> Result<T, Failure<T, E>> {
+        tell_(&self.adventures, &mut Limited::new(&mut Inspected(inspect), &self.settings), acc)
+    }
+    /// Tells a saga like `tell` while recording what happened, the
+    /// events are in the order they occurred.
+    pub fn tell_traced(&self, acc: T) -> (Result<T, Failure<T, E>>, Vec<SagaEvent>) {
+        let mut tracer = Tracer::new();
+        let res = tell_(&self.adventures, &mut Limited::new(&mut tracer, &self.settings), acc);
+        let completed = res.is_ok();
+        (res, tracer.finish(completed))
+    }
+    /// Creates the inverse of the saga, its forward steps are the
+    /// backward steps of this saga in reverse order and the other way
+    /// around.
+    ///
+    /// Backward steps that are handed the error that caused a rollback
+    /// are handed the error `reason` creates instead. Adventures whose backward step
+    /// needs what their forward step produced, like those created with
+    /// `Adventure::with_compensation`, parallel stages and nested sagas,
+    /// pass the accumulator on unchanged in the inverse. Conditions and
+    /// retries are not carried over.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the saga contains single use adventures.
+    pub fn inverse<R>(&self, reason: R) -> Saga<T, E>
+    where
+        T: 'static,
+        E: 'static,
+        R: Fn() -> E + Send + Sync + 'static,
+    {
+        let reason: Reason<E> = Arc::new(reason);
+        Saga::new(self.adventures.iter().rev().map(|a| a.inverse(reason.clone())).collect())
+    }
+    /// Simulates telling the saga by only running the validators of its
+    /// adventures in order, no forward step is run.
+    ///
+    /// Since no forward step runs every validator is handed the same
+    /// accumulator, conditional adventures are validated as well. The
+    /// first validator that fails is returned as a failure at its index
+    /// holding the unchanged accumulator, adventures without a
+    /// validator always pass.
+    pub fn dry_run(&self, acc: T) -> Result<T, Failure<T, E>> {
+        for (i, adventure) in self.adventures.iter().enumerate() {
+            if let Err(error) = adventure.validate(&acc) {
+                return Err(Failure {
+                    failed_at: i,
+                    ..Failure::new(acc, error)
+                });
+            }
+        }
+        Ok(acc)
+    }
+    /// Checks that the backward step of each adventure turns the result
+    /// of its forward step for every sample back into the sample, the
+    /// indices of the adventures that do not are returned.
+    ///
+    /// This runs the actual steps, so it is only meant for sagas whose
+    /// steps have no side effects. Every adventure is checked on its own
+    /// with each sample, adventures that do not apply to a sample or
+    /// whose forward step fails for it are not checked with it. A
+    /// backward step that fails does not invert its forward step, the
+    /// backward steps are handed an `InverseCheck` error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the saga contains single use adventures.
+    pub fn validate_invertible(&self, samples: &[T]) -> Result<(), Vec<usize>>
+    where
+        T: Clone + PartialEq,
+        E: From<InverseCheck>,
+    {
+        let error = E::from(InverseCheck);
+        let mut failed = Vec::new();
+        for (i, adventure) in self.adventures.iter().enumerate() {
+            let mut step = adventure;
+            let inverts = samples.iter().all(|sample| {
+                if !step.applies(sample) {
+                    return true;
+                }
+                let (acc, token) = match Told::forward(&mut step, sample.clone()) {
+                    Ok(forwarded) => forwarded,
+                    Err(_) => return true,
+                };
+                let reverted = Told::backward(&mut step, acc, token, &error);
+                reverted.is_ok_and(|(acc, _)| acc == *sample)
+            });
+            if !inverts {
+                failed.push(i);
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+    /// Tells a saga mutating the accumulator of the caller, after a
+    /// failure it holds the state after the rollback.
+    ///
+    /// The steps of the adventures are handed `acc` by reference, it is
+    /// never moved or cloned. The failure returned carries no state since
+    /// it is left in `acc`. The default backward step of the saga takes
+    /// the accumulator by value and is not used.
+    ///
+    /// # Panics
+    ///
+    /// Panics before any adventure is told when one of them was not
+    /// created with `Adventure::in_place` or has a commit step.
+    pub fn tell_in_place(&self, acc: &mut T) -> Result<(), Failure<(), E>> {
+        let adventures: Vec<_> = self.adventures.iter().map(InPlace::new).collect();
+        let settings = self.settings.borrowed();
+        match tell_(adventures, &mut Limited::new(&mut Unhooked, &settings), acc) {
+            Ok(_) => Ok(()),
+            Err(failure) => Err(failure.map_state(|_| ())),
+        }
+    }
+    /// Tells a saga like `tell` but stops once `cancel` is set, for
+    /// example when a server shuts down.
+    ///
+    /// The flag is checked before each forward step, when it is set no
+    /// further adventure is told and the adventures told so far are
+    /// rolled back. The failure then holds a `Cancelled` error and
+    /// `failed_at` is the index of the first adventure that was not
+    /// told. The flag is usually shared through an `Arc`.
+    pub fn tell_cancellable(&self, acc: T, cancel: &AtomicBool) -> Result<T, Failure<T, E>>
+    where
+        E: From<Cancelled>,
+    {
+        tell_(&self.adventures, &mut Limited::new(&mut Cancellation(cancel), &self.settings), acc)
+    }
+    /// Tells a saga like `tell` but stops once `deadline` passed, the
+    /// budget covers the whole saga instead of a single step.
+    ///
+    /// The deadline is checked before each forward step, once it passed
+    /// no further adventure is told and the adventures told so far are
+    /// rolled back. The failure then holds a `DeadlineExceeded` error
+    /// and `failed_at` is the index of the first adventure that was not
+    /// told. A forward step that is already running is not interrupted,
+    /// combine this with `Adventure::with_timeout` for that.
+    ///
+    /// The rollback always runs to the end, even when the backward steps
+    /// take the saga further past the deadline, as stopping it would
+    /// leave the saga half reverted. To keep the whole saga within a
+    /// budget pass a deadline that leaves enough time for compensating,
+    /// like `Instant::now() + budget - reserve`.
+    #[cfg(feature = "std")]
+    pub fn tell_deadline(&self, acc: T, deadline: Instant) -> Result<T, Failure<T, E>>
+    where
+        E: From<DeadlineExceeded>,
+    {
+        tell_(&self.adventures, &mut Limited::new(&mut Deadline(deadline), &self.settings), acc)
+    }
+    /// Tells a saga consuming it, this allows telling sagas made of
+    /// single use adventures.
+    pub fn tell_owned(self, acc: T) -> Result<T, Failure<T, E>> {
+        tell_(self.adventures, &mut Limited::new(&mut Unhooked, &self.settings), acc)
+    }
+    /// Resumes telling a saga from a saved state, the adventure at the
+    /// index of the state is the first one told.
+    ///
+    /// All adventures before that index are considered told, when the
+    /// saga fails they are reverted as well. This includes conditional
+    /// adventures that might have been skipped before the state was
+    /// saved.
+    pub fn tell_from(&self, state: SagaState<T>) -> Result<T, Failure<T, E>> {
+        let index = state.index.min(self.adventures.len());
+        let (told, steps) = self.adventures.split_at(index);
+        let told = told.iter().enumerate().map(|(i, step)| (i, step, None)).collect();
+        let steps = steps.iter().enumerate().map(|(i, step)| (index + i, step));
+        resume_(told, steps, &mut Limited::new(&mut Unhooked, &self.settings), state.acc).map(|(acc, told)| commit(told, acc))
+    }
+    /// Resumes a rollback that was interrupted, the adventure at the
+    /// index of the state is the first one reverted.
+    ///
+    /// The error that caused the rollback has to be provided again as
+    /// it is handed to the backward steps and returned in the failure.
+    pub fn revert_from(&self, state: SagaState<T>, error: E) -> Failure<T, E> {
+        let end = (state.index + 1).min(self.adventures.len());
+        let told = self.adventures[..end]
+            .iter()
+            .enumerate()
+            .map(|(i, step)| (i, step, None))
+            .collect();
+        revert(told, &mut Defaulted::new(&self.settings), error, state.acc)
+    }
+    /// Reverts a saga that was told successfully before, like when the
+    /// business cancels what the saga did. Only the backward steps run,
+    /// in the same order as for a rollback, starting with `acc` as the
+    /// accumulator the saga returned.
+    ///
+    /// `reason` is handed to the backward steps as the error. The data
+    /// the forward steps kept for the rollback is gone once the saga
+    /// finished, so the backward steps of adventures created with
+    /// `Adventure::with_compensation`, `Adventure::with_input_snapshot`
+    /// or `Adventure::with_compensations` are skipped. The reverted
+    /// accumulator is returned when every backward step succeeded, else
+    /// a failure holding the ones that failed.
+    pub fn revert_all(&self, acc: T, reason: E) -> Result<T, Failure<T, E>> {
+        let told = self
+            .adventures
+            .iter()
+            .enumerate()
+            .map(|(i, step)| (i, step, None))
+            .collect();
+        let failure = revert(told, &mut Defaulted::new(&self.settings), reason, acc);
+        if failure.compensations.is_empty() {
+            Ok(failure.state)
+        } else {
+            Err(failure)
+        }
+    }
+    /// Finishes a rollback that was interrupted, like by a crash while
+    /// compensating, without reverting the adventures after `from_index`
+    /// again.
+    ///
+    /// `acc` is the accumulator saved after the last backward step that
+    /// ran and the adventure at `from_index` is the first one reverted,
+    /// for a rollback that was aborted this is `Failure::aborted_at`.
+    /// Like for `revert_from` the error has to be provided again.
+    pub fn resume_rollback(&self, acc: T, from_index: usize, error: E) -> Failure<T, E> {
+        self.revert_from(SagaState::new(from_index, acc), error)
+    }
+    /// Tells the saga for each item as one atomic batch, when telling
+    /// an item fails all items told before it are reverted as well.
+    ///
+    /// The items told before the failed one are reverted in reverse
+    /// order, each with the adventures that were told for it. The state
+    /// of the failure holds every item: the reverted ones, the failed one
+    /// as its own rollback left it and the ones that were never told
+    /// unchanged. `failed_at` is the index of the item that failed. When
+    /// a compensation fails the rollback stops there and the remaining
+    /// items are left as they were. Commit steps only run once every
+    /// item was told.
+    pub fn tell_each(&self, items: Vec<T>) -> Result<Vec<T>, Failure<Vec<T>, E>> {
+        let mut items = items.into_iter();
+        tell_all(self, &mut items).map_err(|failure| {
+            failure.map_state(|mut reverted| {
+                reverted.extend(items);
+                reverted
+            })
+        })
+    }
+    /// Tells the saga over and over, handing each result on as the next
+    /// accumulator, until `predicate` holds for a result or `max_iters`
+    /// iterations were told.
+    ///
+    /// A failed iteration only reverts its own adventures and its failure
+    /// is returned right away. When the iterations are exhausted the
+    /// result of the last one is returned even though the predicate does
+    /// not hold for it.
+    pub fn tell_until<P>(&self, acc: T, predicate: P, max_iters: usize) -> Result<T, Failure<T, E>>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let mut acc = acc;
+        for _ in 0..max_iters {
+            acc = self.tell(acc)?;
+            if predicate(&acc) {
+                break;
+            }
+        }
+        Ok(acc)
+    }
+}
+
+/// Clones share the steps of the original saga, so the same plan can
+/// be told with many accumulators, also on other threads.
+///
+/// # Panics
+///
+/// Panics when the saga contains single use adventures.
+impl<T, E> Clone for Saga<T, E> {
+    fn clone(&self) -> Self {
+        Saga {
+            adventures: self.adventures.clone(),
+            settings: self.settings.clone(),
+        }
+    }
+}
+
+/// Two sagas are equal when they have equal adventures in the same
+/// order, see the equality of `Adventure`, and the same settings: the
+/// step limit, whether they are strict, the rollback order and the
+/// callbacks of `Saga::on_rollback` and `Saga::with_default_backward`.
+/// Like steps the callbacks are only the same for clones of one saga.
+impl<T, E> PartialEq for Saga<T, E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.adventures == other.adventures
+            && self.settings.limit.map(|limit| limit.max) == other.settings.limit.map(|limit| limit.max)
+            && self.settings.strict.is_some() == other.settings.strict.is_some()
+            && self.settings.order == other.settings.order
+            && same(&self.settings.on_rollback, &other.settings.on_rollback)
+            && same(&self.settings.default_backward, &other.settings.default_backward)
+    }
+}
+
+impl<T, E> Extend<Adventure<T, E>> for Saga<T, E> {
+    fn extend<I: IntoIterator<Item = Adventure<T, E>>>(&mut self, adventures: I) {
+        self.adventures.extend(adventures);
+    }
+}
+
+impl<T, E> fmt::Debug for Saga<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Saga")
+            .field("len", &self.adventures.len())
+            .field("adventures", &self.adventures)
+            .field("max_steps", &self.settings.limit.map(|limit| limit.max))
+            .field("strict", &self.settings.strict.is_some())
+            .field("rollback_order", &self.settings.order)
+            .finish()
+    }
+}
+
+/// Tells the steps for each item pulled from `items`, when telling an
+/// item fails all items told before it are reverted as well. The items
+/// that were not pulled yet are left in `items`.
+pub(crate) fn tell_all<T, E, I>(saga: &Saga<T, E>, items: &mut I) -> Result<Vec<T>, Failure<Vec<T>, E>>
+where
+    I: Iterator<Item = T>,
+{
+    let mut told = Vec::with_capacity(items.size_hint().0);
+    for item in items {
+        let failure = match run_(&saga.adventures, &mut Defaulted::new(&saga.settings), item) {
+            Ok(res) => {
+                told.push(res);
+                continue;
+            }
+            Err(failure) => failure,
+        };
+        let failed_at = told.len();
+        let Failure {
+            mut error,
+            state,
+            mut compensations,
+            origin,
+            mut rolled_back,
+            details,
+            ..
+        } = failure;
+        let mut artifacts = details.map_or_else(Vec::new, |details| details.artifacts);
+        // the rollback of each item runs unhooked so it is never aborted
+        let mut reverted = alloc::vec![state];
+        for (item, steps) in told.into_iter().rev() {
+            if !compensations.is_empty() {
+                reverted.push(item);
+                continue;
+            }
+            let failure = revert(steps, &mut Defaulted::new(&saga.settings), error, item);
+            error = failure.error;
+            compensations = failure.compensations;
+            rolled_back += failure.rolled_back;
+            if let Some(details) = failure.details {
+                artifacts.extend(details.artifacts);
+            }
+            reverted.push(failure.state);
+        }
+        reverted.reverse();
+        let mut failure = Failure {
+            failed_at,
+            origin,
+            compensations,
+            rolled_back,
+            ..Failure::new(reverted, error)
+        };
+        if !artifacts.is_empty() {
+            failure.details_mut().artifacts = artifacts;
+        }
+        return Err(failure);
+    }
+    Ok(told.into_iter().map(|(item, steps)| commit(steps, item)).collect())
+}
+
+/// The steps that were told along with their index and token.
+pub(crate) type Journal<S> = Vec<(usize, S, Option<Token>)>;
+
+pub(crate) fn tell_<T, E, H, S, I>(steps: I, hooks: &mut H, acc: T) -> Result<T, Failure<T, E>>
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+    I: IntoIterator<Item = S>,
+{
+    run_(steps, hooks, acc).map(|(acc, told)| commit(told, acc))
+}
+
+/// Runs the commit steps of all told steps in the order they were told.
+pub(crate) fn commit<T, E, S>(told: Journal<S>, acc: T) -> T
+where
+    S: Told<T, E>,
+{
+    told.into_iter().fold(acc, |acc, (_, mut step, _)| step.commit(acc))
+}
+
+/// Tells all steps like `tell_` but keeps the told steps so they can
+/// be reverted later on.
+pub(crate) fn run_<T, E, H, S, I>(steps: I, hooks: &mut H, acc: T) -> Result<(T, Journal<S>), Failure<T, E>>
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+    I: IntoIterator<Item = S>,
+{
+    let mut steps = steps.into_iter().peekable();
+    if steps.peek().is_none() {
+        if let Some(error) = hooks.empty() {
+            return Err(Failure::new(acc, error));
+        }
+    }
+    let told = Vec::with_capacity(steps.size_hint().0);
+    resume_(told, steps.enumerate(), hooks, acc)
+}
+
+/// Tells the remaining steps, the already told steps are reverted as
+/// well when one of the remaining steps fails.
+fn resume_<T, E, H, S, I>(
+    told: Journal<S>,
+    steps: I,
+    hooks: &mut H,
+    acc: T,
+) -> Result<(T, Journal<S>), Failure<T, E>>
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+    I: Iterator<Item = (usize, S)>,
+{
+    let mut told = told;
+    let mut acc = acc;
+    for (i, step) in steps {
+        match tell_step(&mut told, i, step, hooks, acc) {
+            Stepped::Next(acc1) => acc = acc1,
+            Stepped::Completed(acc) => return Ok((acc, told)),
+            Stepped::Revert {
+                acc,
+                error,
+                failed_at,
+                origin,
+            } => {
+                let failure = revert(told, hooks, error, acc);
+                return Err(Failure {
+                    failed_at: failed_at.unwrap_or(failure.failed_at),
+                    origin,
+                    ..failure
+                });
+            }
+            Stepped::Stalled(failure) => return Err(failure),
+        }
+    }
+    Ok((acc, told))
+}
+
+/// What telling a single step led to.
+pub(crate) enum Stepped<T, E> {
+    /// The step was told or skipped, the saga goes on with the next one
+    Next(T),
+    /// The step completed the saga early
+    Completed(T),
+    /// The saga has to be rolled back from the state of the failure
+    Revert {
+        acc: T,
+        error: E,
+        /// The index of the failure when it is not the last told step
+        failed_at: Option<usize>,
+        origin: Option<Box<Origin>>,
+    },
+    /// A nested rollback stalled, the saga stops without a rollback
+    Stalled(Failure<T, E>),
+}
+
+/// Tells a single step and adds it to the told steps when it has to be
+/// reverted with them.
+pub(crate) fn tell_step<T, E, H, S>(told: &mut Journal<S>, i: usize, mut step: S, hooks: &mut H, acc: T) -> Stepped<T, E>
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+{
+    if !step.applies(&acc) {
+        return Stepped::Next(acc);
+    }
+    if step.committed(&acc) {
+        told.push((i, step, None));
+        return Stepped::Next(acc);
+    }
+    if let Some(error) = hooks.cancelled() {
+        return Stepped::Revert {
+            acc,
+            error,
+            failed_at: Some(i),
+            origin: None,
+        };
+    }
+    hooks.before_forward(i, &acc);
+    let forwarded = match hooks.injected(i) {
+        Some(error) => Err(Failure::new(acc, error)),
+        None => {
+            let (forwarded, attempts) = step.forward_counted(acc);
+            hooks.attempted(i, attempts);
+            forwarded
+        }
+    };
+    match forwarded {
+        Ok((acc, token)) => {
+            hooks.after_forward(i, &acc);
+            let completed = token.as_ref().is_some_and(|token| token.is::<Completed>());
+            if let Err(error) = hooks.checkpoint(i, &acc) {
+                let origin = Origin::new(step.name(), step.phase());
+                told.push((i, step, token));
+                return Stepped::Revert {
+                    acc,
+                    error,
+                    failed_at: None,
+                    origin,
+                };
+            }
+            told.push((i, step, token));
+            if completed {
+                Stepped::Completed(acc)
+            } else {
+                Stepped::Next(acc)
+            }
+        }
+        Err(Failure {
+            state,
+            error,
+            compensations,
+            ..
+        }) if !compensations.is_empty() => {
+            // a nested rollback stalled, so the rollback stops here
+            hooks.on_forward_failure(i, &error);
+            let phase: Option<Box<str>> = step.phase().map(Box::from);
+            let origin = Origin::new(step.name(), step.phase());
+            let compensations = compensations
+                .into_iter()
+                .map(|failed| CompensationFailure {
+                    index: i,
+                    phase: phase.clone(),
+                    error: failed.error,
+                    irreversible: false,
+                })
+                .collect();
+            Stepped::Stalled(Failure {
+                failed_at: i,
+                origin,
+                compensations,
+                ..Failure::new(state, error)
+            })
+        }
+        Err(Failure { state, error, .. }) => {
+            hooks.on_forward_failure(i, &error);
+            let origin = Origin::new(step.name(), step.phase());
+            told.push((i, step, None));
+            Stepped::Revert {
+                acc: state,
+                error,
+                failed_at: None,
+                origin,
+            }
+        }
+    }
+}
+
+pub(crate) fn revert<T, E, H, S>(
+    told: Journal<S>,
+    hooks: &mut H,
+    error: E,
+    acc: T,
+) -> Failure<T, E>
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+{
+    let failed_at = told.last().map_or(0, |(i, _, _)| *i);
+    let undone = undo(told, hooks, &error, acc);
+    rolled_back(failed_at, undone, hooks, error)
+}
+
+/// Turns a finished rollback into the failure of the saga.
+pub(crate) fn rolled_back<T, E, H>(failed_at: usize, undone: Undone<T, E>, hooks: &mut H, error: E) -> Failure<T, E>
+where
+    H: Hooks<T, E> + ?Sized,
+{
+    let error = undone.contexts.into_iter().fold(error, |error, (i, context)| context(error, i));
+    hooks.after_rollback(failed_at, &error, undone.reverted);
+    let mut failure = Failure {
+        failed_at,
+        compensations: undone.failed,
+        aborted_at: undone.aborted_at,
+        rolled_back: undone.reverted,
+        ..Failure::new(undone.acc, error)
+    };
+    if !undone.artifacts.is_empty() {
+        failure.details_mut().artifacts = undone.artifacts;
+    }
+    failure
+}
+
+/// How far a rollback got.
+pub(crate) struct Undone<T, E> {
+    /// The state after the last backward step that ran
+    pub(crate) acc: T,
+    /// The backward steps that failed
+    failed: Vec<CompensationFailure<E>>,
+    /// The index at which the hooks aborted the rollback
+    aborted_at: Option<usize>,
+    /// The number of backward steps that succeeded
+    reverted: usize,
+    /// The artifacts the backward steps created
+    artifacts: Vec<CompArtifact>,
+    /// The error contexts of the reverted steps in the order they ran
+    contexts: Vec<(usize, ErrorContext<E>)>,
+}
+
+impl<T, E> Undone<T, E> {
+    pub(crate) fn new(acc: T) -> Self {
+        Undone {
+            acc,
+            failed: Vec::new(),
+            aborted_at: None,
+            reverted: 0,
+            artifacts: Vec::new(),
+            contexts: Vec::new(),
+        }
+    }
+}
+
+/// Runs the backward steps of all told steps by rollback priority and
+/// in reverse order for the same priority. The rollback stops at the
+/// first backward step that fails, unless the hooks ask for a best
+/// effort rollback, or when the hooks abort it.
+fn undo<T, E, H, S>(told: Journal<S>, hooks: &mut H, error: &E, acc: T) -> Undone<T, E>
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+{
+    let mut undone = Undone::new(acc);
+    for (i, step, token) in rollback_order(told, hooks.rollback_order()) {
+        let (next, go_on) = undo_step(undone, i, step, token, hooks, error);
+        undone = next;
+        if !go_on {
+            break;
+        }
+    }
+    undone
+}
+
+/// Orders the told steps by rollback priority and in the given order
+/// for the same priority.
+pub(crate) fn rollback_order<S, T, E>(told: Journal<S>, order: RollbackOrder) -> Journal<S>
+where
+    S: Told<T, E>,
+{
+    let mut told = told;
+    if order == RollbackOrder::Reverse {
+        told.reverse();
+    }
+    // the sort is stable so steps of the same priority stay in order
+    told.sort_by_key(|(_, step, _)| Reverse(step.priority()));
+    told
+}
+
+/// Runs the backward step of a single told step, returns how far the
+/// rollback got and whether it goes on.
+pub(crate) fn undo_step<T, E, H, S>(
+    undone: Undone<T, E>,
+    i: usize,
+    mut step: S,
+    token: Option<Token>,
+    hooks: &mut H,
+    error: &E,
+) -> (Undone<T, E>, bool)
+where
+    H: Hooks<T, E> + ?Sized,
+    S: Told<T, E>,
+{
+    let mut undone = undone;
+    if hooks.keeps(i) {
+        return (undone, true);
+    }
+    if hooks.before_backward(i).is_err() {
+        undone.aborted_at = Some(i);
+        return (undone, false);
+    }
+    let acc = hooks.restore(i, undone.acc);
+    let reverted = match hooks.default_backward() {
+        Some(backward) if step.deferred() => Ok((backward(acc), Vec::new())),
+        _ => step.backward(acc, token, error),
+    };
+    match reverted {
+        Ok((acc, artifacts)) => {
+            hooks.after_backward(i, &acc);
+            undone.acc = acc;
+            undone.reverted += 1;
+            for mut artifact in artifacts {
+                artifact.index = i;
+                undone.artifacts.push(artifact);
+            }
+            if let Some(context) = step.error_context() {
+                undone.contexts.push((i, context));
+            }
+            (undone, true)
+        }
+        Err(Failure { state, error, .. }) => {
+            hooks.on_backward_failure(i, &error);
+            undone.acc = state;
+            let phase = step.phase().map(Box::from);
+            let irreversible = step.irreversible();
+            undone.failed.push(CompensationFailure {
+                index: i,
+                phase,
+                error,
+                irreversible,
+            });
+            let go_on = hooks.best_effort() || irreversible;
+            (undone, go_on)
+        }
+    }
+}
+
+/// Reverts the steps of a nested saga as part of a single backward
+/// step, a failing backward step is returned as the failure of it.
+pub(crate) fn undo_nested<T, E, S>(told: Journal<S>, hooks: &mut Defaulted<'_, T>, error: &E, acc: T) -> Result<T, Failure<T, E>>
+where
+    S: Told<T, E>,
+{
+    let mut undone = undo(told, hooks, error, acc);
+    match undone.failed.pop() {
+        None => Ok(undone.acc),
+        Some(failed) => Err(Failure::new(undone.acc, failed.error)),
+    }
+}
@@ -0,0 +1,121 @@
+//! Saved progress of a saga so it can be resumed.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The progress of a saga, made of the index of the next adventure and
+/// the accumulator it is handed.
+///
+/// When telling a saga the index is the next adventure to tell, when
+/// reverting it is the next adventure to revert. With the `serde`
+/// feature enabled the state can be persisted to resume a saga with
+/// `Saga::tell_from` or `Saga::revert_from` after a crash.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SagaState<T> {
+    pub(crate) index: usize,
+    pub(crate) acc: T,
+}
+
+impl<T> SagaState<T> {
+    /// Creates a new state from the index of the next adventure and
+    /// the accumulator
+    pub fn new(index: usize, acc: T) -> Self {
+        SagaState { index, acc }
+    }
+    /// The index of the next adventure
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// The accumulator handed to the next adventure
+    pub fn acc(&self) -> &T {
+        &self.acc
+    }
+    /// Turns the state into the accumulator
+    pub fn into_acc(self) -> T {
+        self.acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc2, StupidError};
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Saga;
+    use crate::SagaState;
+    use std::error::Error;
+
+    fn double(i: i32) -> Result<i32, Failure<i32>> {
+        Ok(i * 2)
+    }
+    fn half(i: i32) -> i32 {
+        i / 2
+    }
+    fn saga() -> Saga<i32> {
+        Saga::new(vec![
+            Adventure::new(double, half),
+            Adventure::new(double, half),
+            Adventure::new(inc2, dec),
+        ])
+    }
+    #[test]
+    fn resumes_forward() {
+        match saga().tell_from(SagaState::new(1, 0)) {
+            Ok(res) => assert_eq!(res, 1),
+            Err(_) => unimplemented!(),
+        }
+    }
+    #[test]
+    fn resumed_failure_reverts_everything() {
+        match saga().tell_from(SagaState::new(2, 4)) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, 1);
+            }
+        }
+    }
+    #[test]
+    fn resumes_rollback() {
+        let error: Box<dyn Error> = Box::new(StupidError { stupid: true });
+        let failure = saga().revert_from(SagaState::new(1, 8), error);
+        assert_eq!(failure.state, 2);
+        assert_eq!(failure.failed_at(), 1);
+    }
+    #[test]
+    fn resumes_interrupted_rollback() {
+        use crate::{AbortRollback, Observer};
+        // stands in for a crash right before the adventure at 1 is reverted
+        struct Crash;
+        impl Observer for Crash {
+            fn before_backward(&mut self, index: usize) -> Result<(), AbortRollback> {
+                if index == 1 {
+                    Err(AbortRollback)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+        let failure = saga().tell_with_observer(1, &mut Crash).unwrap_err();
+        assert_eq!(failure.aborted_at(), Some(1));
+        assert_eq!(failure.state, 4);
+        let from = failure.aborted_at().unwrap();
+        let (error, acc) = failure.into_parts();
+        let failure = saga().resume_rollback(acc, from, error);
+        assert_eq!(failure.state, 1);
+        assert_eq!(failure.rolled_back(), 2);
+        assert_eq!(failure.aborted_at(), None);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips() {
+        let state = SagaState::new(2, 42);
+        let json = serde_json::to_string(&state).unwrap();
+        let state: SagaState<i32> = serde_json::from_str(&json).unwrap();
+        match saga().tell_from(state) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => assert_eq!(failure.state, 10),
+        }
+    }
+}
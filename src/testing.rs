@@ -0,0 +1,177 @@
+//! Helpers for testing sagas built with this crate.
+//!
+//! A `MockAdventure` hands out adventures that leave the accumulator
+//! unchanged and count how often their steps were called, so a test
+//! can check which adventures were told and rolled back without
+//! writing steps and error types of its own.
+//!
+//! ```
+//! use aud::testing::MockAdventure;
+//! use aud::Saga;
+//!
+//! let told = MockAdventure::new();
+//! let failing = MockAdventure::new().failing_at(1);
+//! let saga: Saga<i32> = Saga::new(vec![told.adventure(), failing.adventure()]);
+//! assert!(saga.tell(0).is_err());
+//! told.assert_rolled_back();
+//! ```
+
+use crate::{Adventure, Failure};
+use alloc::sync::Arc;
+use core::error::Error;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The error of a mock adventure that was told to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockFailure {
+    invocation: usize,
+}
+
+impl MockFailure {
+    /// The invocation of the forward step that failed, starting at 1
+    pub fn invocation(&self) -> usize {
+        self.invocation
+    }
+}
+
+impl fmt::Display for MockFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "mock failed at invocation {}", self.invocation)
+    }
+}
+
+impl Error for MockFailure {}
+
+#[derive(Debug, Default)]
+struct Calls {
+    forward: AtomicUsize,
+    backward: AtomicUsize,
+}
+
+/// Creates adventures that record how often their steps were called.
+///
+/// Clones share the recorded calls, so do all adventures created from
+/// the same mock.
+#[derive(Debug, Clone, Default)]
+pub struct MockAdventure {
+    calls: Arc<Calls>,
+    fail_at: Option<usize>,
+}
+
+impl MockAdventure {
+    /// Creates a mock whose forward step always succeeds
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes the forward step fail on the given invocation, the first
+    /// invocation is 1
+    pub fn failing_at(mut self, invocation: usize) -> Self {
+        self.fail_at = Some(invocation);
+        self
+    }
+    /// Creates an adventure whose steps hand the accumulator on
+    /// unchanged while recording the call
+    pub fn adventure<T, E>(&self) -> Adventure<T, E>
+    where
+        E: From<MockFailure>,
+    {
+        let forward = self.calls.clone();
+        let backward = self.calls.clone();
+        let fail_at = self.fail_at;
+        Adventure::new(
+            move |acc| {
+                let invocation = forward.forward.fetch_add(1, Ordering::SeqCst) + 1;
+                if fail_at == Some(invocation) {
+                    Err(Failure::from_error(acc, MockFailure { invocation }))
+                } else {
+                    Ok(acc)
+                }
+            },
+            move |acc| {
+                backward.backward.fetch_add(1, Ordering::SeqCst);
+                acc
+            },
+        )
+    }
+    /// How often the forward step was called
+    pub fn forward_calls(&self) -> usize {
+        self.calls.forward.load(Ordering::SeqCst)
+    }
+    /// How often the backward step was called
+    pub fn backward_calls(&self) -> usize {
+        self.calls.backward.load(Ordering::SeqCst)
+    }
+    /// Asserts that the adventure was told and every time it was told it
+    /// was rolled back as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the forward step was never called or the backward
+    /// step was not called as often as the forward step.
+    pub fn assert_rolled_back(&self) {
+        assert!(self.forward_calls() > 0, "the adventure was never told");
+        assert_eq!(
+            self.backward_calls(),
+            self.forward_calls(),
+            "the adventure was not rolled back every time it was told"
+        );
+    }
+    /// Asserts that the adventure was told and never rolled back.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the forward step was never called or the backward
+    /// step was called.
+    pub fn assert_committed(&self) {
+        assert!(self.forward_calls() > 0, "the adventure was never told");
+        assert_eq!(self.backward_calls(), 0, "the adventure was rolled back");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockAdventure;
+    use super::MockFailure;
+    use crate::Saga;
+
+    #[test]
+    fn records_commits() {
+        let first = MockAdventure::new();
+        let second = MockAdventure::new();
+        let saga: Saga<i32> = Saga::new(vec![first.adventure(), second.adventure()]);
+        assert_eq!(saga.tell(1).ok(), Some(1));
+        first.assert_committed();
+        second.assert_committed();
+    }
+    #[test]
+    fn records_rollbacks() {
+        let first = MockAdventure::new();
+        let second = MockAdventure::new().failing_at(2);
+        let saga: Saga<i32> = Saga::new(vec![first.adventure(), second.adventure()]);
+        assert!(saga.tell(1).is_ok());
+        let failure = saga.tell(1).unwrap_err();
+        assert_eq!(
+            failure
+                .error()
+                .downcast_ref::<MockFailure>()
+                .unwrap()
+                .invocation(),
+            2
+        );
+        assert_eq!(first.forward_calls(), 2);
+        assert_eq!(first.backward_calls(), 1);
+        assert_eq!(second.backward_calls(), 1);
+    }
+    #[test]
+    #[should_panic(expected = "the adventure was rolled back")]
+    fn asserts_commits() {
+        let mock = MockAdventure::new();
+        let saga: Saga<i32> = Saga::new(vec![
+            mock.adventure(),
+            MockAdventure::new().failing_at(1).adventure(),
+        ]);
+        assert!(saga.tell(1).is_err());
+        mock.assert_committed();
+    }
+}
@@ -0,0 +1,145 @@
+//! Timings of the steps run while telling a saga.
+
+use crate::hooks::Hooks;
+use crate::{AbortRollback, DeadlineExceeded, Direction};
+use std::time::{Duration, Instant};
+
+/// How long a single step took.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepTiming {
+    index: usize,
+    direction: Direction,
+    duration: Duration,
+}
+
+impl StepTiming {
+    /// The index of the adventure
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Whether the forward or backward step was run
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+    /// How long the step took, for forward steps this includes all
+    /// retries
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// Measures each step while a saga is told.
+pub(crate) struct Timer {
+    started: Option<Instant>,
+    timings: Vec<StepTiming>,
+}
+
+impl Timer {
+    pub(crate) fn new() -> Self {
+        Timer {
+            started: None,
+            timings: Vec::new(),
+        }
+    }
+    pub(crate) fn timings(self) -> Vec<StepTiming> {
+        self.timings
+    }
+    fn start(&mut self) {
+        self.started = Some(Instant::now());
+    }
+    fn stop(&mut self, index: usize, direction: Direction) {
+        if let Some(started) = self.started.take() {
+            self.timings.push(StepTiming {
+                index,
+                direction,
+                duration: started.elapsed(),
+            });
+        }
+    }
+}
+
+impl<T, E> Hooks<T, E> for Timer {
+    fn before_forward(&mut self, _index: usize, _acc: &T) {
+        self.start();
+    }
+    fn after_forward(&mut self, index: usize, _acc: &T) {
+        self.stop(index, Direction::Forward);
+    }
+    fn on_forward_failure(&mut self, index: usize, _error: &E) {
+        self.stop(index, Direction::Forward);
+    }
+    fn before_backward(&mut self, _index: usize) -> Result<(), AbortRollback> {
+        self.start();
+        Ok(())
+    }
+    fn after_backward(&mut self, index: usize, _acc: &T) {
+        self.stop(index, Direction::Backward);
+    }
+    fn on_backward_failure(&mut self, index: usize, _error: &E) {
+        self.stop(index, Direction::Backward);
+    }
+}
+
+/// Stops a saga once its deadline passed.
+pub(crate) struct Deadline(pub(crate) Instant);
+
+impl<T, E> Hooks<T, E> for Deadline
+where
+    E: From<DeadlineExceeded>,
+{
+    fn cancelled(&mut self) -> Option<E> {
+        if Instant::now() >= self.0 {
+            Some(E::from(DeadlineExceeded))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc2};
+    use crate::Adventure;
+    use crate::Direction;
+    use crate::Failure;
+    use crate::Saga;
+    use std::thread;
+    use std::time::Duration;
+
+    fn slow(i: i32) -> Result<i32, Failure<i32>> {
+        thread::sleep(Duration::from_millis(20));
+        Ok(i + 1)
+    }
+    #[test]
+    fn times_forward_steps() {
+        let saga = Saga::new(vec![Adventure::new(inc2, dec), Adventure::new(slow, dec)]);
+        let (res, timings) = saga.tell_timed(0);
+        assert_eq!(res.ok(), Some(2));
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[1].index(), 1);
+        assert_eq!(timings[1].direction(), Direction::Forward);
+        assert!(timings[1].duration() >= Duration::from_millis(20));
+    }
+    #[test]
+    fn times_backward_steps() {
+        let saga = Saga::new(vec![
+            Adventure::new(slow, dec),
+            Adventure::new(inc2, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let (res, timings) = saga.tell_timed(0);
+        assert!(res.is_err());
+        let steps: Vec<_> = timings.iter().map(|t| (t.index(), t.direction())).collect();
+        assert_eq!(
+            steps,
+            vec![
+                (0, Direction::Forward),
+                (1, Direction::Forward),
+                (2, Direction::Forward),
+                (2, Direction::Backward),
+                (1, Direction::Backward),
+                (0, Direction::Backward),
+            ]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Event traces of telling a saga.
+
+use crate::hooks::Hooks;
+use alloc::vec::Vec;
+
+/// Something that happened while a saga was told.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SagaEvent {
+    /// The saga was started
+    Started,
+    /// The forward step of the adventure at the index succeeded
+    ForwardOk(usize),
+    /// The forward step of the adventure at the index failed
+    ForwardFailed(usize),
+    /// The backward step of the adventure at the index succeeded
+    BackwardDone(usize),
+    /// Every adventure was told
+    Completed,
+    /// The saga failed and was rolled back as far as possible
+    RolledBack,
+}
+
+/// Records the events while a saga is told.
+pub(crate) struct Tracer {
+    events: Vec<SagaEvent>,
+}
+
+impl Tracer {
+    pub(crate) fn new() -> Self {
+        Tracer {
+            events: alloc::vec![SagaEvent::Started],
+        }
+    }
+    pub(crate) fn finish(mut self, completed: bool) -> Vec<SagaEvent> {
+        self.events.push(if completed {
+            SagaEvent::Completed
+        } else {
+            SagaEvent::RolledBack
+        });
+        self.events
+    }
+}
+
+impl<T, E> Hooks<T, E> for Tracer {
+    fn after_forward(&mut self, index: usize, _acc: &T) {
+        self.events.push(SagaEvent::ForwardOk(index));
+    }
+    fn on_forward_failure(&mut self, index: usize, _error: &E) {
+        self.events.push(SagaEvent::ForwardFailed(index));
+    }
+    fn after_backward(&mut self, index: usize, _acc: &T) {
+        self.events.push(SagaEvent::BackwardDone(index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::plain::{dec, inc, inc2};
+    use crate::Adventure;
+    use crate::Saga;
+    use crate::SagaEvent::*;
+
+    #[test]
+    fn traces_completed() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(inc2, dec)]);
+        let (res, events) = saga.tell_traced(0);
+        assert_eq!(res.ok(), Some(2));
+        assert_eq!(events, vec![Started, ForwardOk(0), ForwardOk(1), Completed]);
+    }
+    #[test]
+    fn traces_rollback() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(inc2, dec),
+        ]);
+        let (res, events) = saga.tell_traced(0);
+        assert!(res.is_err());
+        assert_eq!(
+            events,
+            vec![
+                Started,
+                ForwardOk(0),
+                ForwardOk(1),
+                ForwardFailed(2),
+                BackwardDone(2),
+                BackwardDone(1),
+                BackwardDone(0),
+                RolledBack,
+            ]
+        );
+    }
+}
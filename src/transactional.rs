@@ -0,0 +1,261 @@
+//! Sagas told inside a transaction.
+//!
+//! When a saga is backed by a database most of its steps write to the
+//! same transaction. A transactional saga owns the transaction for the
+//! time it is told and hands it to every forward step. Once all steps
+//! succeeded the transaction is committed, when a step fails it is
+//! rolled back.
+//!
+//! The backward steps are not handed the transaction since everything
+//! written to it is undone by rolling it back, they only compensate the
+//! side effects outside of it like sent mails or calls to other
+//! services. They run before the transaction is rolled back, and also
+//! when committing the transaction fails.
+
+use crate::adventure::{Forwarded, Reverted, Token, Told};
+use crate::hooks::Unhooked;
+use crate::saga::{revert, run_};
+use crate::{Failure, Saga};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::error::Error;
+use core::fmt;
+
+/// A transaction a saga can be told in.
+pub trait Transactional {
+    /// The error committing or rolling back the transaction fails with
+    type Error;
+    /// Makes everything written to the transaction permanent
+    fn commit(self) -> Result<(), Self::Error>;
+    /// Discards everything written to the transaction
+    fn rollback(self) -> Result<(), Self::Error>;
+}
+
+type TxnForward<X, T, E> = Rc<dyn Fn(&mut X, T) -> Result<T, Failure<T, E>>>;
+type TxnBackward<T> = Rc<dyn Fn(T) -> T>;
+
+/// An adventure whose forward step is handed the transaction.
+pub struct TxnAdventure<X, T, E = Box<dyn Error>> {
+    forward: TxnForward<X, T, E>,
+    backward: TxnBackward<T>,
+}
+
+impl<X, T, E> TxnAdventure<X, T, E> {
+    /// Creates a new adventure from a forward step that writes to the
+    /// transaction and a backward step that compensates what happened
+    /// outside of it
+    pub fn new<F, B>(forward: F, backward: B) -> Self
+    where
+        F: Fn(&mut X, T) -> Result<T, Failure<T, E>> + 'static,
+        B: Fn(T) -> T + 'static,
+    {
+        TxnAdventure {
+            forward: Rc::new(forward),
+            backward: Rc::new(backward),
+        }
+    }
+}
+
+impl<X, T, E> Clone for TxnAdventure<X, T, E> {
+    fn clone(&self) -> Self {
+        TxnAdventure {
+            forward: self.forward.clone(),
+            backward: self.backward.clone(),
+        }
+    }
+}
+
+impl<X, T, E> fmt::Debug for TxnAdventure<X, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TxnAdventure").finish_non_exhaustive()
+    }
+}
+
+/// A saga of adventures that write to the same transaction.
+pub struct TxnSaga<X, T, E = Box<dyn Error>> {
+    adventures: Vec<TxnAdventure<X, T, E>>,
+}
+
+impl<T, E> Saga<T, E> {
+    /// Creates a saga whose adventures are handed a transaction, it is
+    /// passed in when the saga is told
+    pub fn transactional<X>(adventures: Vec<TxnAdventure<X, T, E>>) -> TxnSaga<X, T, E> {
+        TxnSaga { adventures }
+    }
+}
+
+impl<X, T, E> TxnSaga<X, T, E> {
+    /// The number of adventures in the saga
+    pub fn len(&self) -> usize {
+        self.adventures.len()
+    }
+    /// Whether the saga has no adventures
+    pub fn is_empty(&self) -> bool {
+        self.adventures.is_empty()
+    }
+    /// Tells the saga inside `txn` and commits it when all steps
+    /// succeeded.
+    ///
+    /// When a step fails the saga is reverted and the transaction rolled
+    /// back, an error rolling it back is dropped as the failure already
+    /// holds the error of the step. When committing fails the saga is
+    /// reverted with the error of the commit.
+    pub fn tell_in_txn(&self, txn: X, acc: T) -> Result<T, Failure<T, E>>
+    where
+        X: Transactional,
+        E: From<X::Error>,
+    {
+        // the told steps keep borrowing the cell until they are reverted,
+        // so the transaction is taken out of it to be committed
+        let cell = RefCell::new(Some(txn));
+        let steps = self.adventures.iter().map(|adventure| InTxn {
+            adventure,
+            txn: &cell,
+        });
+        match run_(steps, &mut Unhooked, acc) {
+            Ok((acc, told)) => match cell.take().map_or(Ok(()), X::commit) {
+                Ok(()) => Ok(acc),
+                Err(error) => Err(revert(told, &mut Unhooked, E::from(error), acc)),
+            },
+            Err(failure) => {
+                let _ = cell.take().map(X::rollback);
+                Err(failure)
+            }
+        }
+    }
+}
+
+impl<X, T, E> Clone for TxnSaga<X, T, E> {
+    fn clone(&self) -> Self {
+        TxnSaga {
+            adventures: self.adventures.clone(),
+        }
+    }
+}
+
+impl<X, T, E> fmt::Debug for TxnSaga<X, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TxnSaga")
+            .field("adventures", &self.adventures)
+            .finish()
+    }
+}
+
+/// An adventure paired with the transaction it is told in.
+struct InTxn<'a, X, T, E> {
+    adventure: &'a TxnAdventure<X, T, E>,
+    txn: &'a RefCell<Option<X>>,
+}
+
+impl<X, T, E> Told<T, E> for InTxn<'_, X, T, E> {
+    fn applies(&self, _acc: &T) -> bool {
+        true
+    }
+    fn priority(&self) -> i32 {
+        0
+    }
+    fn forward(&mut self, acc: T) -> Forwarded<T, E> {
+        let mut txn = self.txn.borrow_mut();
+        let txn = txn.as_mut().expect("the transaction is only taken after the saga was told");
+        (self.adventure.forward)(txn, acc).map(|acc| (acc, None))
+    }
+    fn backward(&mut self, acc: T, _token: Option<Token>, _error: &E) -> Reverted<T, E> {
+        Ok(((self.adventure.backward)(acc), Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Failure;
+    use crate::Saga;
+    use crate::{Transactional, TxnAdventure};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Outcome = Rc<RefCell<Option<(&'static str, Vec<i32>)>>>;
+
+    /// Records what happened to the transaction.
+    #[derive(Default)]
+    struct Txn {
+        writes: Vec<i32>,
+        fails: bool,
+        outcome: Outcome,
+    }
+
+    impl Transactional for Txn {
+        type Error = &'static str;
+        fn commit(self) -> Result<(), Self::Error> {
+            if self.fails {
+                return Err("commit failed");
+            }
+            *self.outcome.borrow_mut() = Some(("committed", self.writes));
+            Ok(())
+        }
+        fn rollback(self) -> Result<(), Self::Error> {
+            *self.outcome.borrow_mut() = Some(("rolled back", self.writes));
+            Ok(())
+        }
+    }
+
+    fn write(txn: &mut Txn, i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        txn.writes.push(i);
+        Ok(i + 1)
+    }
+    fn fail(_txn: &mut Txn, i: i32) -> Result<i32, Failure<i32, &'static str>> {
+        Err(Failure::new(i, "step failed"))
+    }
+    fn sub(i: i32) -> i32 {
+        i - 1
+    }
+    #[test]
+    fn commits_txn() {
+        let saga = Saga::transactional(vec![
+            TxnAdventure::new(write, sub),
+            TxnAdventure::new(write, sub),
+        ]);
+        let txn = Txn::default();
+        let outcome = txn.outcome.clone();
+        assert_eq!(saga.tell_in_txn(txn, 0).ok(), Some(2));
+        assert_eq!(*outcome.borrow(), Some(("committed", vec![0, 1])));
+    }
+    #[test]
+    fn rolls_back_txn() {
+        let saga = Saga::transactional(vec![
+            TxnAdventure::new(write, sub),
+            TxnAdventure::new(fail, sub),
+        ]);
+        let txn = Txn::default();
+        let outcome = txn.outcome.clone();
+        match saga.tell_in_txn(txn, 0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.error, "step failed");
+                assert_eq!(failure.state, -1);
+            }
+        }
+        assert_eq!(*outcome.borrow(), Some(("rolled back", vec![0])));
+    }
+    #[test]
+    fn reverts_failed_commit() {
+        let saga = Saga::transactional(vec![
+            TxnAdventure::new(write, sub),
+            TxnAdventure::new(write, sub),
+        ]);
+        let txn = Txn {
+            fails: true,
+            ..Txn::default()
+        };
+        let outcome = txn.outcome.clone();
+        match saga.tell_in_txn(txn, 0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.error, "commit failed");
+                assert_eq!(failure.failed_at(), 1);
+                assert_eq!(failure.state, 0);
+            }
+        }
+        assert_eq!(*outcome.borrow(), None);
+    }
+}
@@ -0,0 +1,283 @@
+//! Telling sagas so that panicking steps are rolled back.
+//!
+//! A forward step that panics would normally unwind through the saga
+//! without a single compensation running. When a saga is told with
+//! `Saga::tell_catch_unwind` each forward step runs inside
+//! `std::panic::catch_unwind` instead and a panic is turned into a
+//! `Panicked` error that rolls the saga back like any other failure.
+//!
+//! Since the accumulator is moved into the step it is lost when the
+//! step panics, so it is cloned before every forward step and the clone
+//! is handed to the rollback. The accumulator has to be `UnwindSafe` as
+//! it may be observed after a panic. The steps themselves are assumed to
+//! be unwind safe, a step created with `Adventure::new_mut` that panics
+//! halfway through changing its captured state leaves that state as it
+//! was at the panic. Backward steps are not guarded, a panic during the
+//! rollback still unwinds, unless the saga is told with
+//! `Saga::tell_isolated`.
+//!
+//! An isolated backward step runs on a thread of its own that is joined
+//! right away, a panic ends that thread and is taken from the join
+//! instead of unwinding the caller. As the step does not run inside
+//! `catch_unwind` neither the step nor its panic payload have to be
+//! unwind safe, but the accumulator and the error have to be `Send`,
+//! and the error `Sync` as the step borrows it. A backward step that
+//! hangs still blocks the rollback.
+
+use crate::adventure::{ErrorContext, Forwarded, Reverted, Told, Token};
+use crate::hooks::{Limited, Unhooked};
+use crate::saga::tell_;
+use crate::{Failure, Panicked, Saga};
+use std::panic::{self, AssertUnwindSafe, UnwindSafe};
+use std::thread;
+
+/// A told step whose forward step catches panics.
+struct CatchUnwind<S>(S);
+
+/// A told step whose forward step catches panics and whose backward step
+/// runs on a thread of its own.
+struct Isolated<S>(CatchUnwind<S>);
+
+impl<T, E, S> Told<T, E> for CatchUnwind<S>
+where
+    T: Clone + UnwindSafe,
+    E: From<Panicked>,
+    S: Told<T, E>,
+{
+    fn applies(&self, acc: &T) -> bool {
+        self.0.applies(acc)
+    }
+    fn committed(&self, acc: &T) -> bool {
+        self.0.committed(acc)
+    }
+    fn name(&self) -> Option<&str> {
+        self.0.name()
+    }
+    fn phase(&self) -> Option<&str> {
+        self.0.phase()
+    }
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+    fn irreversible(&self) -> bool {
+        self.0.irreversible()
+    }
+    fn deferred(&self) -> bool {
+        self.0.deferred()
+    }
+    fn error_context(&self) -> Option<ErrorContext<E>> {
+        self.0.error_context()
+    }
+    fn commit(&mut self, acc: T) -> T {
+        self.0.commit(acc)
+    }
+    fn forward(&mut self, acc: T) -> Forwarded<T, E> {
+        self.forward_counted(acc).0
+    }
+    fn forward_counted(&mut self, acc: T) -> (Forwarded<T, E>, u32) {
+        let state = acc.clone();
+        let step = &mut self.0;
+        match panic::catch_unwind(AssertUnwindSafe(move || step.forward_counted(acc))) {
+            Ok(res) => res,
+            Err(payload) => (Err(Failure::from_error(state, Panicked::new(payload))), 1),
+        }
+    }
+    fn backward(&mut self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E> {
+        self.0.backward(acc, token, error)
+    }
+}
+
+impl<T, E, S> Told<T, E> for Isolated<S>
+where
+    T: Clone + UnwindSafe + Send,
+    E: From<Panicked> + Send + Sync,
+    S: Told<T, E> + Send,
+{
+    fn applies(&self, acc: &T) -> bool {
+        self.0.applies(acc)
+    }
+    fn committed(&self, acc: &T) -> bool {
+        self.0.committed(acc)
+    }
+    fn name(&self) -> Option<&str> {
+        self.0.name()
+    }
+    fn phase(&self) -> Option<&str> {
+        self.0.phase()
+    }
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+    fn irreversible(&self) -> bool {
+        self.0.irreversible()
+    }
+    fn deferred(&self) -> bool {
+        self.0.deferred()
+    }
+    fn error_context(&self) -> Option<ErrorContext<E>> {
+        self.0.error_context()
+    }
+    fn commit(&mut self, acc: T) -> T {
+        self.0.commit(acc)
+    }
+    fn forward(&mut self, acc: T) -> Forwarded<T, E> {
+        self.forward_counted(acc).0
+    }
+    fn forward_counted(&mut self, acc: T) -> (Forwarded<T, E>, u32) {
+        self.0.forward_counted(acc)
+    }
+    fn backward(&mut self, acc: T, token: Option<Token>, error: &E) -> Reverted<T, E> {
+        let state = acc.clone();
+        let CatchUnwind(step) = &mut self.0;
+        match thread::scope(|scope| scope.spawn(move || step.backward(acc, token, error)).join()) {
+            Ok(res) => res,
+            Err(payload) => Err(Failure::from_error(state, Panicked::new(payload))),
+        }
+    }
+}
+
+impl<T, E> Saga<T, E>
+where
+    T: Clone + UnwindSafe,
+    E: From<Panicked>,
+{
+    /// Tells a saga like `tell` but turns a panicking forward step into
+    /// a `Panicked` error that rolls the saga back.
+    ///
+    /// The accumulator is cloned before each forward step, the clone is
+    /// the state handed to the rollback when the step panics.
+    pub fn tell_catch_unwind(&self, acc: T) -> Result<T, Failure<T, E>> {
+        tell_(
+            self.adventures.iter().map(CatchUnwind),
+            &mut Limited::new(&mut Unhooked, &self.settings),
+            acc,
+        )
+    }
+}
+
+impl<T, E> Saga<T, E>
+where
+    T: Clone + UnwindSafe + Send,
+    E: From<Panicked> + Send + Sync,
+{
+    /// Tells a saga like `tell_catch_unwind` and also runs every backward
+    /// step on a thread of its own, a panicking backward step becomes a
+    /// failed compensation with a `Panicked` error.
+    ///
+    /// The rollback stops at the panicking backward step like at any
+    /// other failed compensation, the failure holds the accumulator as
+    /// it was handed to that step.
+    pub fn tell_isolated(&self, acc: T) -> Result<T, Failure<T, E>> {
+        tell_(
+            self.adventures
+                .iter()
+                .map(|adventure| Isolated(CatchUnwind(adventure))),
+            &mut Limited::new(&mut Unhooked, &self.settings),
+            acc,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{dec, inc};
+    use crate::Adventure;
+    use crate::Failure;
+    use crate::Panicked;
+    use crate::Saga;
+    use std::error::Error;
+
+    /// An error that can be borrowed by a backward step on another thread
+    type SendError = Box<dyn Error + Send + Sync>;
+
+    fn explode(i: i32) -> Result<i32, Failure<i32>> {
+        if i >= 2 {
+            panic!("exploded at {}", i);
+        }
+        Ok(i + 1)
+    }
+    #[test]
+    fn rolls_back_panics() {
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, dec),
+            Adventure::new(explode, dec),
+        ]);
+        match saga.tell_catch_unwind(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, -1);
+                let panicked = failure.error().downcast_ref::<Panicked>().unwrap();
+                assert_eq!(panicked.message(), Some("exploded at 2"));
+            }
+        }
+    }
+    #[test]
+    fn isolates_panicking_compensations() {
+        fn explode(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            if i >= 2 {
+                panic!("exploded at {}", i);
+            }
+            Ok(i + 1)
+        }
+        fn inc(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            Ok(i + 1)
+        }
+        fn implode(i: i32) -> i32 {
+            panic!("imploded at {}", i);
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, implode),
+            Adventure::new(explode, dec),
+        ]);
+        match saga.tell_isolated(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.failed_at(), 2);
+                assert_eq!(failure.state, 1);
+                assert_eq!(failure.rolled_back(), 1);
+                let compensation = failure.compensation_failure().unwrap();
+                assert_eq!(compensation.index(), 1);
+                let panicked = compensation.error().downcast_ref::<Panicked>().unwrap();
+                assert_eq!(panicked.message(), Some("imploded at 1"));
+            }
+        }
+    }
+    #[test]
+    fn isolates_payloads_that_are_not_unwind_safe() {
+        /// A payload that is not `UnwindSafe` as it holds an `FnMut`
+        struct Torn(#[allow(dead_code)] Box<dyn FnMut() + Send>);
+        fn inc(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            Ok(i + 1)
+        }
+        fn fail(i: i32) -> Result<i32, Failure<i32, SendError>> {
+            Err(Failure::new(i, "failed".into()))
+        }
+        fn tear(_: i32) -> i32 {
+            std::panic::panic_any(Torn(Box::new(|| ())));
+        }
+        let saga = Saga::new(vec![
+            Adventure::new(inc, dec),
+            Adventure::new(inc, tear),
+            Adventure::new(fail, dec),
+        ]);
+        match saga.tell_isolated(0) {
+            Ok(_) => unimplemented!(),
+            Err(failure) => {
+                assert_eq!(failure.state, 1);
+                assert_eq!(failure.rolled_back(), 1);
+                let compensation = failure.compensation_failure().unwrap();
+                assert_eq!(compensation.index(), 1);
+                let panicked = compensation.error().downcast_ref::<Panicked>().unwrap();
+                assert_eq!(panicked.message(), None);
+            }
+        }
+    }
+    #[test]
+    fn tells_without_panics() {
+        let saga = Saga::new(vec![Adventure::new(inc, dec), Adventure::new(explode, dec)]);
+        assert_eq!(saga.tell_catch_unwind(0).ok(), Some(2));
+    }
+}
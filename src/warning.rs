@@ -0,0 +1,37 @@
+//! Non-fatal warnings of forward steps.
+
+use alloc::string::String;
+use core::fmt;
+
+/// A warning a forward step created with `Adventure::with_warnings`
+/// returned next to its result, like a deprecation or partial data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub(crate) index: usize,
+    message: String,
+}
+
+impl Warning {
+    /// Creates a new warning with a message
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Warning {
+            index: 0,
+            message: message.into(),
+        }
+    }
+    /// The message of the warning
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    /// The index of the adventure whose forward step returned the
+    /// warning, it is set when the saga is told
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step {}: {}", self.index, self.message)
+    }
+}